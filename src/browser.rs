@@ -1,41 +1,706 @@
+use super::fs_ops::{self, DirSize};
+use super::thumbnails::{self, Thumbnails};
 use eframe::egui::{self, Context, Key, Ui};
 use std::{
+    cmp,
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
+const THUMB_SIDE: f32 = 32.0;
+const GRID_CELL: f32 = 96.0;
+const GRID_ICON: f32 = 64.0;
+const TYPE_AHEAD_WINDOW: Duration = Duration::from_secs(1);
+
 #[derive(PartialEq)]
 enum Interaction {
     None,
     Rename { path: PathBuf, buffer: String },
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Extension,
+    Modified,
+    Size,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 4] = [
+        SortKey::Name,
+        SortKey::Extension,
+        SortKey::Modified,
+        SortKey::Size,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Extension => "extension",
+            SortKey::Modified => "modified",
+            SortKey::Size => "size",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.key() == key)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Folder,
+    Image,
+    Document,
+    Audio,
+    Video,
+    Archive,
+    Code,
+    Other,
+}
+
+impl FileKind {
+    const FILTERS: [FileKind; 7] = [
+        FileKind::Folder,
+        FileKind::Image,
+        FileKind::Document,
+        FileKind::Audio,
+        FileKind::Video,
+        FileKind::Archive,
+        FileKind::Code,
+    ];
+
+    pub fn of(path: &Path, is_dir: bool) -> Self {
+        if is_dir {
+            return FileKind::Folder;
+        }
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" | "tif" | "tiff"
+            | "heic" => FileKind::Image,
+            "pdf" | "doc" | "docx" | "odt" | "rtf" | "txt" | "md" | "xls" | "xlsx" | "ods"
+            | "ppt" | "pptx" | "odp" | "csv" | "epub" => FileKind::Document,
+            "mp3" | "wav" | "flac" | "ogg" | "m4a" | "aac" | "opus" | "wma" => FileKind::Audio,
+            "mp4" | "mkv" | "avi" | "mov" | "webm" | "wmv" | "flv" | "m4v" => FileKind::Video,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "7z" | "rar" | "zst" => FileKind::Archive,
+            "rs" | "c" | "h" | "cpp" | "hpp" | "cc" | "py" | "js" | "ts" | "jsx" | "tsx"
+            | "java" | "go" | "rb" | "php" | "cs" | "swift" | "kt" | "sh" | "bash" | "zsh"
+            | "toml" | "json" | "yaml" | "yml" | "xml" | "html" | "css" | "lua" | "sql" | "ini" => {
+                FileKind::Code
+            }
+            _ => FileKind::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileKind::Folder => "Folders",
+            FileKind::Image => "Images",
+            FileKind::Document => "Documents",
+            FileKind::Audio => "Audio",
+            FileKind::Video => "Video",
+            FileKind::Archive => "Archives",
+            FileKind::Code => "Code",
+            FileKind::Other => "Other",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum IconSet {
+    Emoji,
+    Glyph,
+    None,
+}
+
+impl IconSet {
+    pub const ALL: [IconSet; 3] = [IconSet::Emoji, IconSet::Glyph, IconSet::None];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            IconSet::Emoji => "emoji",
+            IconSet::Glyph => "glyph",
+            IconSet::None => "none",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            IconSet::Emoji => "Emoji",
+            IconSet::Glyph => "Plain symbols",
+            IconSet::None => "No icons",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ViewKind {
+    List,
+    Details,
+    Grid,
+}
+
+impl ViewKind {
+    pub const ALL: [ViewKind; 3] = [ViewKind::List, ViewKind::Details, ViewKind::Grid];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            ViewKind::List => "list",
+            ViewKind::Details => "details",
+            ViewKind::Grid => "grid",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ViewKind::List => ViewKind::Details,
+            ViewKind::Details => ViewKind::Grid,
+            ViewKind::Grid => ViewKind::List,
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|v| v.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewKind::List => "☰ List",
+            ViewKind::Details => "▦ Details",
+            ViewKind::Grid => "▩ Grid",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct EntryInfo {
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+    mode: Option<u32>,
+}
+
+pub fn icon_for(kind: FileKind, set: IconSet) -> &'static str {
+    match set {
+        IconSet::Emoji => match kind {
+            FileKind::Folder => "📁",
+            FileKind::Image => "🖼",
+            FileKind::Audio => "🎵",
+            FileKind::Video => "🎞",
+            FileKind::Archive => "📦",
+            FileKind::Code => "📜",
+            FileKind::Document | FileKind::Other => "📄",
+        },
+        IconSet::Glyph => match kind {
+            FileKind::Folder => "▣",
+            FileKind::Image => "◩",
+            FileKind::Audio => "♪",
+            FileKind::Video => "▶",
+            FileKind::Archive => "▤",
+            FileKind::Code => "≡",
+            FileKind::Document | FileKind::Other => "▢",
+        },
+        IconSet::None => "",
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ChildCount {
+    files: usize,
+    folders: usize,
+}
+
+struct ChildCounts {
+    /// Counts keyed by folder, with the folder's mtime when it was counted.
+    cache: HashMap<PathBuf, (Option<SystemTime>, ChildCount)>,
+    pending: HashSet<PathBuf>,
+    tx_req: Sender<PathBuf>,
+    rx_done: Receiver<(PathBuf, Option<SystemTime>, ChildCount)>,
+}
+
+impl ChildCounts {
+    fn new(workers: usize) -> Self {
+        let (tx_req, rx_req) = mpsc::channel::<PathBuf>();
+        let (tx_done, rx_done) = mpsc::channel();
+        let rx_req = Arc::new(Mutex::new(rx_req));
+        for _ in 0..workers.max(1) {
+            let rx_req = rx_req.clone();
+            let tx_done = tx_done.clone();
+            std::thread::spawn(move || {
+                loop {
+                    let Ok(dir) = rx_req.lock().unwrap().recv() else {
+                        break;
+                    };
+                    let modified = fs::symlink_metadata(&dir).and_then(|m| m.modified()).ok();
+                    let mut count = ChildCount {
+                        files: 0,
+                        folders: 0,
+                    };
+                    for e in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                        if e.file_type().is_ok_and(|t| t.is_dir()) {
+                            count.folders += 1;
+                        } else {
+                            count.files += 1;
+                        }
+                    }
+                    if tx_done.send((dir, modified, count)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Self {
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            tx_req,
+            rx_done,
+        }
+    }
+
+    fn poll(&mut self) {
+        while let Ok((dir, modified, count)) = self.rx_done.try_recv() {
+            self.pending.remove(&dir);
+            self.cache.insert(dir, (modified, count));
+        }
+    }
+
+    fn get(&mut self, dir: &Path) -> Option<ChildCount> {
+        if let Some((_, count)) = self.cache.get(dir) {
+            return Some(*count);
+        }
+        if self.pending.insert(dir.to_path_buf()) {
+            let _ = self.tx_req.send(dir.to_path_buf());
+        }
+        None
+    }
+
+    /// Drops the counts of `parent`'s subfolders that are gone or whose mtime
+    /// moved (a folder's mtime changes when entries are added or removed).
+    /// Counts for folders elsewhere are left alone.
+    fn refresh(&mut self, parent: &Path, listed: &HashMap<PathBuf, Option<SystemTime>>) {
+        self.cache.retain(|dir, (modified, _)| {
+            dir.parent() != Some(parent) || listed.get(dir).is_some_and(|m| *m == *modified)
+        });
+    }
+}
+
+struct FolderSizes {
+    cache: HashMap<PathBuf, DirSize>,
+    pending: HashSet<PathBuf>,
+    abort: Arc<AtomicBool>,
+    tx: Sender<(PathBuf, DirSize)>,
+    rx: Receiver<(PathBuf, DirSize)>,
+}
+
+impl FolderSizes {
+    fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            cache: HashMap::new(),
+            pending: HashSet::new(),
+            abort: Arc::new(AtomicBool::new(false)),
+            tx,
+            rx,
+        }
+    }
+
+    fn request(&mut self, dir: PathBuf) {
+        if !self.pending.insert(dir.clone()) {
+            return;
+        }
+        let abort = self.abort.clone();
+        let tx = self.tx.clone();
+        std::thread::spawn(move || {
+            if let Some(size) = fs_ops::dir_size(&dir, &abort) {
+                let _ = tx.send((dir, size));
+            }
+        });
+    }
+
+    fn poll(&mut self) -> Option<(PathBuf, DirSize)> {
+        let (dir, size) = self.rx.try_recv().ok()?;
+        self.pending.remove(&dir);
+        self.cache.insert(dir.clone(), size);
+        Some((dir, size))
+    }
+
+    fn cancel(&mut self) {
+        self.abort.store(true, Ordering::Relaxed);
+        self.abort = Arc::new(AtomicBool::new(false));
+        self.pending.clear();
+    }
+}
+
+struct ListRequest {
+    cwd: PathBuf,
+    show_hidden: bool,
+    // None picks a sort from the folder's name and contents.
+    sort: Option<(SortKey, bool)>,
+    dirs_first: bool,
+    no_ext_first: bool,
+}
+
+struct Listing {
+    cwd: PathBuf,
+    sort: (SortKey, bool),
+    entries: Vec<fs::DirEntry>,
+    info: Vec<EntryInfo>,
+}
+
+/// Reads folders on a worker thread so slow or network folders don't stall
+/// the UI. Only the result of the latest request is ever used.
+struct Lister {
+    generation: u64,
+    requested: Option<PathBuf>,
+    tx_req: Sender<(u64, ListRequest, Context)>,
+    rx_done: Receiver<(u64, Listing)>,
+}
+
+impl Lister {
+    fn new() -> Self {
+        let (tx_req, rx_req) = mpsc::channel::<(u64, ListRequest, Context)>();
+        let (tx_done, rx_done) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Ok(mut req) = rx_req.recv() {
+                while let Ok(newer) = rx_req.try_recv() {
+                    req = newer;
+                }
+                let (generation, req, ctx) = req;
+                if tx_done
+                    .send((generation, FileBrowser::read_listing(&req)))
+                    .is_err()
+                {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            generation: 0,
+            requested: None,
+            tx_req,
+            rx_done,
+        }
+    }
+
+    fn request(&mut self, ctx: &Context, req: ListRequest) {
+        self.generation += 1;
+        self.requested = Some(req.cwd.clone());
+        let _ = self.tx_req.send((self.generation, req, ctx.clone()));
+    }
+
+    fn poll(&mut self) -> Option<Listing> {
+        let mut latest = None;
+        while let Ok((generation, listing)) = self.rx_done.try_recv() {
+            if generation == self.generation {
+                latest = Some(listing);
+            }
+        }
+        latest
+    }
+}
+
 pub struct FileBrowser {
     entries: Vec<fs::DirEntry>,
-    pub selected: Option<usize>,
+    // Metadata for each entry, read once per reload for the details view.
+    info: Vec<EntryInfo>,
+    loaded: bool,
+    lister: Lister,
+    reapply_smart_sort: bool,
+    pub selected: BTreeSet<usize>,
+    anchor: Option<usize>,
+    scroll_to: Option<usize>,
+    type_ahead: String,
+    type_ahead_at: Option<Instant>,
+    quick_filter: String,
     interaction: Interaction,
     last_path: Option<PathBuf>,
+    pub sort_key: SortKey,
+    pub sort_desc: bool,
+    pub smart_sort: bool,
+    sort_overrides: HashMap<PathBuf, (SortKey, bool)>,
+    pub no_ext_first: bool,
+    pub dirs_first: bool,
+    pub show_hidden: bool,
+    dragged_out: bool,
+    pub kind_filter: Option<FileKind>,
+    select_hint: Option<PathBuf>,
+    pub icon_set: IconSet,
+    child_counts: ChildCounts,
+    thumbnails: Thumbnails,
+    folder_sizes: FolderSizes,
+    io_concurrency: usize,
+    pub view_kind: ViewKind,
+}
+
+/// Compares names case-insensitively, treating runs of ASCII digits as numbers
+/// so `file2` sorts before `file10`.
+pub fn natural_cmp(a: &str, b: &str) -> cmp::Ordering {
+    let (mut x, mut y) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return cmp::Ordering::Less,
+            (Some(_), None) => return cmp::Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let run = |it: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = it.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits
+                };
+                let (m, n) = (run(&mut x), run(&mut y));
+                let (m, n) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                let order = m.len().cmp(&n.len()).then_with(|| m.cmp(n));
+                if order != cmp::Ordering::Equal {
+                    return order;
+                }
+            }
+            (Some(c), Some(d)) => {
+                let order = c.to_lowercase().cmp(d.to_lowercase());
+                if order != cmp::Ordering::Equal {
+                    return order;
+                }
+                x.next();
+                y.next();
+            }
+        }
+    }
+}
+
+fn extension_key(path: &Path, no_ext_first: bool) -> (bool, String) {
+    match path.extension() {
+        Some(ext) => (no_ext_first, ext.to_string_lossy().to_lowercase()),
+        None => (!no_ext_first, String::new()),
+    }
 }
 
 impl FileBrowser {
     pub fn new() -> Self {
         Self {
             entries: Vec::new(),
-            selected: None,
+            info: Vec::new(),
+            loaded: false,
+            lister: Lister::new(),
+            reapply_smart_sort: false,
+            selected: BTreeSet::new(),
+            anchor: None,
+            scroll_to: None,
+            type_ahead: String::new(),
+            type_ahead_at: None,
+            quick_filter: String::new(),
             interaction: Interaction::None,
             last_path: None,
+            sort_key: SortKey::Name,
+            sort_desc: false,
+            smart_sort: false,
+            sort_overrides: HashMap::new(),
+            no_ext_first: true,
+            dirs_first: true,
+            show_hidden: false,
+            dragged_out: false,
+            kind_filter: None,
+            select_hint: None,
+            icon_set: IconSet::Emoji,
+            child_counts: ChildCounts::new(1),
+            thumbnails: Thumbnails::new(1),
+            folder_sizes: FolderSizes::new(),
+            io_concurrency: 1,
+            view_kind: ViewKind::List,
         }
     }
     pub fn invalidate(&mut self) {
-        self.entries.clear();
+        self.loaded = false;
+        self.cancel_interaction();
+    }
+    pub fn set_io_concurrency(&mut self, workers: usize) {
+        if workers != self.io_concurrency {
+            self.io_concurrency = workers;
+            self.child_counts = ChildCounts::new(workers);
+            self.thumbnails = Thumbnails::new(workers);
+        }
+    }
+    pub fn select_after_reload(&mut self, path: PathBuf) {
+        self.select_hint = Some(path);
+    }
+    pub fn neighbour_of(&self, path: &Path) -> Option<PathBuf> {
+        let i = self.entries.iter().position(|e| e.path() == path)?;
+        self.entries
+            .get(i + 1)
+            .or_else(|| i.checked_sub(1).and_then(|j| self.entries.get(j)))
+            .map(|e| e.path())
+    }
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        self.selected
+            .iter()
+            .filter_map(|&i| self.entries.get(i))
+            .map(|e| e.path())
+            .collect()
+    }
+    pub fn selected_entry(&self) -> Option<(PathBuf, Option<SystemTime>)> {
+        if self.selected.len() != 1 {
+            return None;
+        }
+        let index = *self.selected.first()?;
+        let entry = self.entries.get(index)?;
+        let info = self.info.get(index).copied().unwrap_or_default();
+        Some((entry.path(), info.modified))
+    }
+    fn select_only(&mut self, index: Option<usize>) {
+        self.selected = index.into_iter().collect();
+        self.anchor = index;
+    }
+    pub fn cancel_interaction(&mut self) {
+        self.interaction = Interaction::None;
     }
-    fn reload(&mut self, cwd: &Path) {
-        let mut all = fs::read_dir(cwd)
+    pub fn is_renaming(&self) -> bool {
+        matches!(self.interaction, Interaction::Rename { .. })
+    }
+    fn smart_sort_for(cwd: &Path, entries: &[fs::DirEntry]) -> (SortKey, bool) {
+        let name = cwd
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name == "downloads" || name == "download" {
+            return (SortKey::Modified, true);
+        }
+        let files: Vec<FileKind> = entries
+            .iter()
+            .map(|e| e.path())
+            .filter(|p| !p.is_dir())
+            .map(|p| FileKind::of(&p, false))
+            .collect();
+        let media = files
+            .iter()
+            .filter(|k| matches!(k, FileKind::Image | FileKind::Video))
+            .count();
+        if !files.is_empty() && media * 2 > files.len() {
+            (SortKey::Modified, false)
+        } else {
+            (SortKey::Name, false)
+        }
+    }
+
+    fn read_listing(req: &ListRequest) -> Listing {
+        let cwd = req.cwd.as_path();
+        let all = fs::read_dir(cwd)
             .unwrap_or_else(|_| fs::read_dir(Path::new("/")).unwrap())
             .filter_map(Result::ok)
+            .filter(|e| req.show_hidden || !super::platform::is_hidden(e))
             .collect::<Vec<_>>();
-        all.sort_by_key(|e| (!e.path().is_dir(), e.path()));
-        self.entries = all;
+        let (sort_key, sort_desc) = req.sort.unwrap_or_else(|| Self::smart_sort_for(cwd, &all));
+        let no_ext_first = req.no_ext_first;
+        let mut keyed: Vec<_> = all
+            .into_iter()
+            .map(|e| {
+                let path = e.path();
+                let ext = match sort_key {
+                    SortKey::Extension => Some(extension_key(&path, no_ext_first)),
+                    _ => None,
+                };
+                let meta = e.metadata().ok();
+                let info = EntryInfo {
+                    is_dir: path.is_dir(),
+                    size: meta.as_ref().map_or(0, |m| m.len()),
+                    modified: meta.as_ref().and_then(|m| m.modified().ok()),
+                    mode: meta.as_ref().and_then(fs_ops::mode_of),
+                };
+                let name = e.file_name().to_string_lossy().to_string();
+                (info.is_dir, ext, info, name, e)
+            })
+            .collect();
+        keyed.sort_by(|a, b| {
+            let primary = match sort_key {
+                SortKey::Name => natural_cmp(&a.3, &b.3),
+                SortKey::Extension => a.1.cmp(&b.1),
+                SortKey::Modified => a.2.modified.cmp(&b.2.modified),
+                // A folder's own size is just its inode, so folders rank below
+                // every file and among themselves by name.
+                SortKey::Size => match (a.0, b.0) {
+                    (false, false) => a.2.size.cmp(&b.2.size),
+                    (true, true) => natural_cmp(&a.3, &b.3),
+                    (true, false) => cmp::Ordering::Less,
+                    (false, true) => cmp::Ordering::Greater,
+                },
+            };
+            let primary = if sort_desc {
+                primary.reverse()
+            } else {
+                primary
+            };
+            let folders = if req.dirs_first {
+                b.0.cmp(&a.0)
+            } else {
+                cmp::Ordering::Equal
+            };
+            folders.then(primary).then_with(|| natural_cmp(&a.3, &b.3))
+        });
+        let (info, entries) = keyed
+            .into_iter()
+            .map(|(_, _, info, _, e)| (info, e))
+            .unzip();
+        Listing {
+            cwd: cwd.to_path_buf(),
+            sort: (sort_key, sort_desc),
+            entries,
+            info,
+        }
+    }
+
+    fn apply_listing(&mut self, listing: Listing) {
+        let navigated = self.last_path.as_deref() != Some(listing.cwd.as_path());
+        // Indices change on reload, so the selection is carried over by path.
+        let previous: HashSet<PathBuf> = if navigated {
+            HashSet::new()
+        } else {
+            self.selected_paths().into_iter().collect()
+        };
+        let anchor = self
+            .anchor
+            .and_then(|i| self.entries.get(i))
+            .map(|e| e.path());
+        (self.sort_key, self.sort_desc) = listing.sort;
+        let listed: HashMap<_, _> = listing
+            .entries
+            .iter()
+            .zip(&listing.info)
+            .map(|(e, info)| (e.path(), info.modified))
+            .collect();
+        self.child_counts.refresh(&listing.cwd, &listed);
+        self.entries = listing.entries;
+        self.info = listing.info;
+        self.last_path = Some(listing.cwd);
+        if let Some(hint) = self.select_hint.take() {
+            let hinted = self.entries.iter().position(|e| e.path() == hint);
+            self.select_only(hinted);
+        } else {
+            self.selected = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| previous.contains(&e.path()))
+                .map(|(i, _)| i)
+                .collect();
+            self.anchor = anchor
+                .filter(|_| !navigated)
+                .and_then(|a| self.entries.iter().position(|e| e.path() == a));
+        }
     }
 
     pub fn update(
@@ -46,42 +711,244 @@ impl FileBrowser {
         on_open: &mut Option<PathBuf>,
         on_pin: &mut Option<PathBuf>,
         on_rename_request: &mut Option<(PathBuf, String)>,
-        on_delete_request: &mut Option<PathBuf>,
+        on_delete_request: &mut Option<(Vec<PathBuf>, bool)>,
         on_open_with_request: &mut Option<PathBuf>,
+        on_extract_request: &mut Option<PathBuf>,
+        on_compress_request: &mut Option<Vec<PathBuf>>,
+        on_hash_request: &mut Option<(Vec<PathBuf>, fs_ops::HashKind)>,
         on_open_terminal: &mut Option<PathBuf>,
 
-        on_copy_request: &mut Option<PathBuf>,
-        on_cut_request: &mut Option<PathBuf>,
+        on_copy_request: &mut Option<Vec<PathBuf>>,
+        on_cut_request: &mut Option<Vec<PathBuf>>,
+        on_copy_structure_request: &mut Option<PathBuf>,
         on_paste_here: &mut Option<PathBuf>,
+        on_symlink_here: &mut Option<PathBuf>,
         on_undo_request: &mut bool,
+        on_redo_request: &mut bool,
+        undo_hint: Option<&str>,
+        redo_hint: Option<&str>,
+        on_go_up: &mut bool,
         has_clipboard: bool,
         on_new_folder_here: &mut Option<PathBuf>,
         on_new_file_here: &mut Option<PathBuf>,
+        on_flatten_request: &mut Option<PathBuf>,
+        on_mtime_request: &mut Option<PathBuf>,
+        on_permissions_request: &mut Option<PathBuf>,
+        on_duplicate_request: &mut Option<PathBuf>,
+        on_tag_request: &mut Option<PathBuf>,
+        on_drag_out: &mut Option<PathBuf>,
+        on_folder_size: &mut Option<(PathBuf, DirSize)>,
+        on_copy_text: &mut Option<(String, &'static str)>,
+        error_paths: &HashMap<PathBuf, String>,
+        tags: &HashMap<PathBuf, String>,
     ) {
-        if self.entries.is_empty()
-            || !cwd.exists()
-            || self.last_path.as_ref().map_or(true, |p| p != cwd)
+        let entering = self.lister.requested.as_deref() != Some(cwd);
+        if !self.loaded || entering {
+            if entering {
+                self.type_ahead.clear();
+                self.quick_filter.clear();
+                self.folder_sizes.cancel();
+            }
+            let navigated = self.last_path.as_deref() != Some(cwd);
+            let pick = self.smart_sort && (navigated || self.reapply_smart_sort);
+            self.reapply_smart_sort = false;
+            let sort = match self.sort_overrides.get(cwd) {
+                Some(sort) if pick => Some(*sort),
+                None if pick => None,
+                _ => Some((self.sort_key, self.sort_desc)),
+            };
+            self.lister.request(
+                ctx,
+                ListRequest {
+                    cwd: cwd.to_path_buf(),
+                    show_hidden: self.show_hidden,
+                    sort,
+                    dirs_first: self.dirs_first,
+                    no_ext_first: self.no_ext_first,
+                },
+            );
+            self.loaded = true;
+        }
+        if let Some(listing) = self.lister.poll() {
+            self.apply_listing(listing);
+        }
+        // A refresh keeps showing the old listing until the new one is in.
+        if self.last_path.as_deref() != Some(cwd) {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Loading directory…");
+            });
+            return;
+        }
+
+        if !ctx.input(|i| i.pointer.any_down()) {
+            self.dragged_out = false;
+        }
+
+        self.child_counts.poll();
+        self.thumbnails.poll(ctx);
+        *on_folder_size = self.folder_sizes.poll();
+        if !self.child_counts.pending.is_empty()
+            || self.thumbnails.is_loading()
+            || !self.folder_sizes.pending.is_empty()
         {
-            self.reload(cwd);
-            self.last_path = Some(cwd.to_path_buf());
-            self.selected = None;
+            ctx.request_repaint_after(Duration::from_millis(100));
         }
 
-        let snapshot: Vec<(usize, PathBuf, bool, String)> = self
+        let kind_filter = self.kind_filter;
+        let quick_filter = self.quick_filter.to_lowercase();
+        let snapshot: Vec<(usize, PathBuf, bool, String, FileKind)> = self
             .entries
             .iter()
             .enumerate()
             .map(|(i, e)| {
                 let path = e.path();
-                let is_dir = path.is_dir();
+                let is_dir = self.info.get(i).is_some_and(|info| info.is_dir);
                 let name = path
                     .file_name()
                     .unwrap_or_default()
                     .to_string_lossy()
                     .to_string();
-                (i, path, is_dir, name)
+                let kind = FileKind::of(&path, is_dir);
+                (i, path, is_dir, name, kind)
+            })
+            .filter(|(.., name, kind)| {
+                kind_filter.is_none_or(|k| k == *kind)
+                    && name.to_lowercase().contains(&quick_filter)
             })
             .collect();
+        // Rows hidden by a filter drop out of the selection, so actions never
+        // reach items the user can't see.
+        if kind_filter.is_some() || !quick_filter.is_empty() {
+            self.selected
+                .retain(|i| snapshot.binary_search_by_key(i, |(j, ..)| *j).is_ok());
+        }
+
+        if self.interaction == Interaction::None && !ctx.wants_keyboard_input() {
+            let typed: String = ctx.input(|i| {
+                if i.modifiers.command {
+                    return String::new();
+                }
+                i.events
+                    .iter()
+                    .filter_map(|e| match e {
+                        egui::Event::Text(t) => Some(t.as_str()),
+                        _ => None,
+                    })
+                    .collect()
+            });
+            if !typed.is_empty() {
+                if self
+                    .type_ahead_at
+                    .is_none_or(|at| at.elapsed() > TYPE_AHEAD_WINDOW)
+                {
+                    self.type_ahead.clear();
+                }
+                self.type_ahead.push_str(&typed.to_lowercase());
+                self.type_ahead_at = Some(Instant::now());
+                let hit = snapshot
+                    .iter()
+                    .find(|(.., name, _)| name.to_lowercase().starts_with(&self.type_ahead))
+                    .map(|(i, ..)| *i);
+                if let Some(index) = hit {
+                    self.select_only(Some(index));
+                    self.scroll_to = Some(index);
+                }
+            }
+            let (select_all, clear) = ctx.input(|i| {
+                (
+                    i.modifiers.command && i.key_pressed(Key::A),
+                    i.key_pressed(Key::Escape),
+                )
+            });
+            if select_all {
+                self.selected = snapshot.iter().map(|(i, ..)| *i).collect();
+            } else if clear {
+                self.select_only(None);
+            }
+            let (up, down, home, end, enter, back) = ctx.input(|i| {
+                let plain = !i.modifiers.alt;
+                (
+                    i.key_pressed(Key::ArrowUp),
+                    i.key_pressed(Key::ArrowDown),
+                    plain && i.key_pressed(Key::Home),
+                    plain && i.key_pressed(Key::End),
+                    i.key_pressed(Key::Enter),
+                    i.key_pressed(Key::Backspace),
+                )
+            });
+            let pos = self
+                .anchor
+                .and_then(|a| snapshot.iter().position(|(i, ..)| *i == a));
+            let last = snapshot.len().saturating_sub(1);
+            let moved = match pos {
+                _ if home => Some(0),
+                _ if end => Some(last),
+                Some(p) if up => Some(p.saturating_sub(1)),
+                Some(p) if down => Some((p + 1).min(last)),
+                None if up || down => Some(0),
+                _ => None,
+            };
+            if let Some((index, ..)) = moved.and_then(|p| snapshot.get(p)) {
+                self.select_only(Some(*index));
+                self.scroll_to = Some(*index);
+            }
+            if enter && let Some((_, path, is_dir, ..)) = pos.and_then(|p| snapshot.get(p)) {
+                if *is_dir {
+                    *on_open = Some(path.clone());
+                } else {
+                    super::platform::open_file(path);
+                }
+            }
+            if back {
+                *on_go_up = true;
+            }
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.kind_filter, None, "All");
+            for kind in FileKind::FILTERS {
+                ui.selectable_value(&mut self.kind_filter, Some(kind), kind.label());
+            }
+            ui.separator();
+            let filter = ui.add(
+                egui::TextEdit::singleline(&mut self.quick_filter)
+                    .hint_text("Filter this folder")
+                    .desired_width(140.0),
+            );
+            if filter.changed() {
+                ctx.request_repaint();
+            }
+            if !self.quick_filter.is_empty() {
+                ui.weak(format!("{} of {}", snapshot.len(), self.entries.len()));
+                if ui.small_button("✖").on_hover_text("Clear filter").clicked() {
+                    self.quick_filter.clear();
+                }
+            }
+            if ui
+                .toggle_value(&mut self.show_hidden, "Hidden files")
+                .on_hover_text("Show dotfiles and hidden files (Ctrl+H)")
+                .changed()
+            {
+                self.invalidate();
+            }
+            if !self.selected.is_empty() {
+                ui.weak(format!(
+                    "{} of {} selected",
+                    self.selected.len(),
+                    snapshot.len()
+                ));
+            }
+            if ui
+                .button(self.view_kind.label())
+                .on_hover_text("Switch between list, details and grid views")
+                .clicked()
+            {
+                self.view_kind = self.view_kind.next();
+            }
+        });
+        let details = self.view_kind == ViewKind::Details;
+        let grid = self.view_kind == ViewKind::Grid;
 
         let in_rename = egui::ScrollArea::vertical()
             .auto_shrink([false, true])
@@ -99,6 +966,14 @@ impl FileBrowser {
                         *on_paste_here = Some(cwd.to_path_buf());
                         ui.close_menu();
                     }
+                    if ui
+                        .add_enabled(has_clipboard, egui::Button::new("🔗 Create symlink here"))
+                        .on_hover_text("Link to the items in the buffer")
+                        .clicked()
+                    {
+                        *on_symlink_here = Some(cwd.to_path_buf());
+                        ui.close_menu();
+                    }
                     if ui.button("📄 New file...").clicked() {
                         *on_new_file_here = Some(cwd.to_path_buf());
                         ui.close_menu();
@@ -116,6 +991,45 @@ impl FileBrowser {
                         self.invalidate();
                         ui.close_menu();
                     }
+                    ui.menu_button("⇅ Sort by", |ui| {
+                        let mut changed = false;
+                        changed |= ui
+                            .radio_value(&mut self.sort_key, SortKey::Name, "Name")
+                            .clicked();
+                        changed |= ui
+                            .radio_value(&mut self.sort_key, SortKey::Extension, "Extension")
+                            .clicked();
+                        changed |= ui
+                            .radio_value(&mut self.sort_key, SortKey::Modified, "Date modified")
+                            .clicked();
+                        changed |= ui
+                            .radio_value(&mut self.sort_key, SortKey::Size, "Size")
+                            .clicked();
+                        changed |= ui.checkbox(&mut self.sort_desc, "Descending").changed();
+                        if changed && self.smart_sort {
+                            self.sort_overrides
+                                .insert(cwd.to_path_buf(), (self.sort_key, self.sort_desc));
+                        }
+                        ui.separator();
+                        changed |= ui
+                            .checkbox(&mut self.no_ext_first, "Files without extension first")
+                            .changed();
+                        changed |= ui.checkbox(&mut self.dirs_first, "Folders first").changed();
+                        if ui
+                            .checkbox(&mut self.smart_sort, "Smart default sort")
+                            .on_hover_text(
+                                "Pick a sort from each folder's name and contents; \
+                                 a sort chosen here for a folder always wins",
+                            )
+                            .changed()
+                        {
+                            self.reapply_smart_sort = true;
+                            changed = true;
+                        }
+                        if changed {
+                            self.invalidate();
+                        }
+                    });
 
                     if let Some(parent) = cwd.parent() {
                         ui.separator();
@@ -134,140 +1048,524 @@ impl FileBrowser {
                 });
 
                 if bg_resp.clicked() {
-                    self.selected = None;
+                    self.select_only(None);
                 }
 
-                for (i, path, is_dir, name) in snapshot {
-                    let icon = if is_dir { "📁" } else { "📄" };
-                    let label = format!("{icon} {name}");
-
-                    let response: egui::Response = ui
-                        .with_layout(egui::Layout::left_to_right(egui::Align::Min), |ui| {
-                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
-                            ui.add(egui::SelectableLabel::new(
-                                self.selected == Some(i),
-                                label.clone(),
-                            ))
-                        })
-                        .inner;
-                    if response.clicked() {
-                        self.selected = Some(i);
-                    }
-
-                    if response.double_clicked() {
-                        if is_dir {
-                            *on_open = Some(path.clone());
-                        } else {
-                            super::platform::open_file(&path);
+                let rows = |ui: &mut Ui| {
+                    if details {
+                        for (key, title) in [
+                            (SortKey::Name, "Name"),
+                            (SortKey::Extension, "Extension"),
+                            (SortKey::Size, "Size"),
+                            (SortKey::Modified, "Modified"),
+                        ] {
+                            let arrow = match (self.sort_key == key, self.sort_desc) {
+                                (true, false) => " ⏶",
+                                (true, true) => " ⏷",
+                                (false, _) => "",
+                            };
+                            let header = egui::RichText::new(format!("{title}{arrow}")).strong();
+                            if ui.add(egui::Button::new(header).frame(false)).clicked() {
+                                if self.sort_key == key {
+                                    self.sort_desc = !self.sort_desc;
+                                } else {
+                                    self.sort_key = key;
+                                    self.sort_desc = false;
+                                }
+                                if self.smart_sort {
+                                    self.sort_overrides
+                                        .insert(cwd.to_path_buf(), (self.sort_key, self.sort_desc));
+                                }
+                                self.invalidate();
+                            }
+                        }
+                        if cfg!(unix) {
+                            ui.strong("Mode");
                         }
+                        ui.end_row();
                     }
+                    for (i, path, is_dir, name, kind) in snapshot {
+                        let info = self.info.get(i).copied().unwrap_or_default();
+                        // Only rows on screen ask for a thumbnail, so scrolling
+                        // through a big folder doesn't churn the cache.
+                        let side = if grid { GRID_ICON } else { THUMB_SIDE };
+                        let row = egui::Rect::from_min_size(ui.cursor().min, egui::vec2(1.0, side));
+                        let thumb = if self.icon_set != IconSet::None
+                            && thumbnails::supports(&path)
+                            && ui.is_rect_visible(row)
+                        {
+                            self.thumbnails.get(&path, info.modified)
+                        } else {
+                            None
+                        };
+                        let icon = match icon_for(kind, self.icon_set) {
+                            _ if thumb.is_some() || grid => String::new(),
+                            "" => String::new(),
+                            icon => format!("{icon} "),
+                        };
+                        let error = error_paths.get(&path);
+                        let tag = tags.get(&path).map(|t| format!(" {t}")).unwrap_or_default();
+                        let label = match error {
+                            Some(_) => egui::RichText::new(format!("⚠ {icon}{name}{tag}"))
+                                .color(ui.visuals().error_fg_color),
+                            None => egui::RichText::new(format!("{icon}{name}{tag}")),
+                        };
 
-                    response.context_menu(|ui| {
-                        if ui.button("📝 Rename").clicked() {
-                            let initial = name.clone();
-                            self.interaction = Interaction::Rename {
-                                path: path.clone(),
-                                buffer: initial,
+                        let selected = self.selected.contains(&i);
+                        let big_icon = icon_for(kind, self.icon_set);
+                        let cell = |ui: &mut Ui| {
+                            ui.set_min_size(egui::Vec2::splat(GRID_CELL));
+                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Truncate);
+                            let top = match &thumb {
+                                Some(texture) => ui.add(
+                                    egui::Image::from_texture(texture)
+                                        .fit_to_exact_size(egui::Vec2::splat(GRID_ICON))
+                                        .sense(egui::Sense::click()),
+                                ),
+                                None => ui.add(
+                                    egui::Label::new(
+                                        egui::RichText::new(big_icon).size(GRID_ICON * 0.75),
+                                    )
+                                    .sense(egui::Sense::click()),
+                                ),
                             };
-                            ui.close_menu();
+                            ui.add(egui::SelectableLabel::new(selected, label.clone()))
+                                .union(top)
+                        };
+                        let mut response: egui::Response = if grid {
+                            ui.allocate_ui_with_layout(
+                                egui::Vec2::splat(GRID_CELL),
+                                egui::Layout::top_down(egui::Align::Center),
+                                cell,
+                            )
+                            .inner
+                            .interact(egui::Sense::drag())
+                        } else {
+                            ui.with_layout(egui::Layout::left_to_right(egui::Align::Min), |ui| {
+                                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Wrap);
+                                let image = thumb.as_ref().map(|texture| {
+                                    ui.add(
+                                        egui::Image::from_texture(texture)
+                                            .fit_to_exact_size(egui::Vec2::splat(THUMB_SIDE))
+                                            .sense(egui::Sense::click()),
+                                    )
+                                });
+                                let mut resp =
+                                    ui.add(egui::SelectableLabel::new(selected, label.clone()));
+                                if let Some(image) = image {
+                                    resp = resp.union(image);
+                                }
+                                if is_dir && self.view_kind == ViewKind::List {
+                                    let count = match self.child_counts.get(&path) {
+                                        Some(c) => {
+                                            format!("{} files, {} folders", c.files, c.folders)
+                                        }
+                                        None => "—".to_string(),
+                                    };
+                                    ui.weak(count);
+                                }
+                                resp
+                            })
+                            .inner
+                            .interact(egui::Sense::drag())
+                        };
+                        if details {
+                            let ext = match path.extension() {
+                                Some(ext) if !is_dir => ext.to_string_lossy().to_string(),
+                                _ => String::new(),
+                            };
+                            ui.weak(ext);
+                            let size = if let Some(size) = self.folder_sizes.cache.get(&path) {
+                                if size.partial {
+                                    format!("≥ {}", fs_ops::format_size(size.bytes))
+                                } else {
+                                    fs_ops::format_size(size.bytes)
+                                }
+                            } else if is_dir {
+                                match self.child_counts.get(&path) {
+                                    Some(c) => format!("{} items", c.files + c.folders),
+                                    None => "—".to_string(),
+                                }
+                            } else {
+                                fs_ops::format_size(info.size)
+                            };
+                            ui.weak(size);
+                            let modified = info
+                                .modified
+                                .map(|t| fs_ops::DateTime::from_system_time(t).to_string())
+                                .unwrap_or_default();
+                            ui.weak(modified);
+                            if let Some(mode) = info.mode {
+                                ui.monospace(fs_ops::format_mode(mode));
+                            }
+                            ui.end_row();
                         }
-                        if ui.button("📎 Open with...").clicked() {
-                            *on_open_with_request = Some(path.clone());
-                            ui.close_menu();
+                        if let Some(reason) = error {
+                            response = response.on_hover_text(reason);
                         }
-                        if ui.button("❌ Delete").clicked() {
-                            *on_delete_request = Some(path.clone());
-                            ui.close_menu();
+                        if self.scroll_to == Some(i) {
+                            self.scroll_to = None;
+                            response.scroll_to_me(None);
                         }
-                        if is_dir && ui.button("📌 Pin").clicked() {
-                            *on_pin = Some(path.clone());
-                            ui.close_menu();
+                        response.dnd_set_drag_payload(path.clone());
+                        if super::platform::CAN_DRAG_OUT && response.dragged() && !self.dragged_out
+                        {
+                            let outside = ctx
+                                .input(|i| i.pointer.latest_pos())
+                                .is_some_and(|pos| !ctx.screen_rect().contains(pos));
+                            if outside {
+                                self.dragged_out = true;
+                                *on_drag_out = Some(path.clone());
+                            }
                         }
-                        if is_dir && ui.button("🖥 Terminal here").clicked() {
-                            *on_open_terminal = Some(path.clone());
-                            ui.close_menu();
+                        if response.clicked() {
+                            let modifiers = ctx.input(|i| i.modifiers);
+                            if modifiers.shift
+                                && let Some(anchor) = self.anchor
+                            {
+                                self.selected = (anchor.min(i)..=anchor.max(i)).collect();
+                            } else if modifiers.command {
+                                if !self.selected.remove(&i) {
+                                    self.selected.insert(i);
+                                }
+                                self.anchor = Some(i);
+                            } else {
+                                self.select_only(Some(i));
+                            }
                         }
-
-                        ui.separator();
-
-                        if ui.button("📄 Copy").clicked() {
-                            *on_copy_request = Some(path.clone());
-                            ui.close_menu();
+                        if response.secondary_clicked() && !self.selected.contains(&i) {
+                            self.select_only(Some(i));
                         }
-                        if ui.button("✂ Cut").clicked() {
-                            *on_cut_request = Some(path.clone());
-                            ui.close_menu();
+
+                        if response.double_clicked() {
+                            if is_dir {
+                                *on_open = Some(path.clone());
+                            } else {
+                                super::platform::open_file(&path);
+                            }
                         }
-                        if is_dir {
+
+                        response.context_menu(|ui| {
+                            // Actions that can take several items apply to the
+                            // whole selection when this row is part of it.
+                            let targets = if self.selected.contains(&i) {
+                                self.selected_paths()
+                            } else {
+                                vec![path.clone()]
+                            };
+                            let count = match targets.len() {
+                                1 => String::new(),
+                                n => format!(" {n} items"),
+                            };
+                            if ui.button("📝 Rename").clicked() {
+                                let initial = name.clone();
+                                self.interaction = Interaction::Rename {
+                                    path: path.clone(),
+                                    buffer: initial,
+                                };
+                                ui.close_menu();
+                            }
+                            if ui.button("📎 Open with...").clicked() {
+                                *on_open_with_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if !is_dir
+                                && fs_ops::is_zip(&path)
+                                && ui.button("📦 Extract here").clicked()
+                            {
+                                *on_extract_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if !is_dir {
+                                ui.menu_button("#️⃣ Compute hash", |ui| {
+                                    for kind in fs_ops::HashKind::ALL {
+                                        if ui.button(kind.label()).clicked() {
+                                            *on_hash_request = Some((targets.clone(), kind));
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            }
+                            if ui.button(format!("🗜 Compress{count} to zip")).clicked() {
+                                *on_compress_request = Some(targets.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button(format!("❌ Delete{count}")).clicked() {
+                                *on_delete_request = Some((targets.clone(), false));
+                                ui.close_menu();
+                            }
+                            if ui
+                                .button(format!("⚠ Delete{count} permanently..."))
+                                .clicked()
+                            {
+                                *on_delete_request = Some((targets.clone(), true));
+                                ui.close_menu();
+                            }
+                            if is_dir && ui.button("📌 Pin").clicked() {
+                                *on_pin = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if is_dir && ui.button("🏷 Set tag...").clicked() {
+                                *on_tag_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if is_dir && ui.button("🖥 Terminal here").clicked() {
+                                *on_open_terminal = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if is_dir && ui.button("📏 Compute size").clicked() {
+                                self.folder_sizes.request(path.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button("🕓 Change modified date...").clicked() {
+                                *on_mtime_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if cfg!(unix) && ui.button("🔐 Permissions...").clicked() {
+                                *on_permissions_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+
                             ui.separator();
-                            if ui.button("📁 New folder here...").clicked() {
-                                *on_new_folder_here = Some(path.clone());
+
+                            if ui.button(format!("📄 Copy{count}")).clicked() {
+                                *on_copy_request = Some(targets.clone());
                                 ui.close_menu();
                             }
-                            if ui.button("📄 New file here...").clicked() {
-                                *on_new_file_here = Some(path.clone());
+                            if ui.button(format!("✂ Cut{count}")).clicked() {
+                                *on_cut_request = Some(targets.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button("🔗 Copy full path").clicked() {
+                                let text = targets
+                                    .iter()
+                                    .map(|p| {
+                                        std::path::absolute(p)
+                                            .unwrap_or_else(|_| p.clone())
+                                            .display()
+                                            .to_string()
+                                    })
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
+                                *on_copy_text = Some((text, "Path copied"));
+                                ui.close_menu();
+                            }
+                            if ui.button("🔤 Copy name").clicked() {
+                                *on_copy_text = Some((name.clone(), "Name copied"));
+                                ui.close_menu();
+                            }
+                            if ui.button("⧉ Duplicate").clicked() {
+                                *on_duplicate_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if is_dir && ui.button("🗂 Copy folder structure").clicked() {
+                                *on_copy_structure_request = Some(path.clone());
+                                ui.close_menu();
+                            }
+                            if is_dir {
+                                ui.separator();
+                                if ui.button("📁 New folder here...").clicked() {
+                                    *on_new_folder_here = Some(path.clone());
+                                    ui.close_menu();
+                                }
+                                if ui.button("📄 New file here...").clicked() {
+                                    *on_new_file_here = Some(path.clone());
+                                    ui.close_menu();
+                                }
+                                if ui.button("⤒ Flatten here...").clicked() {
+                                    *on_flatten_request = Some(path.clone());
+                                    ui.close_menu();
+                                }
+                            }
+                            let target_dir = if is_dir {
+                                path.clone()
+                            } else {
+                                path.parent().unwrap_or(cwd).to_path_buf()
+                            };
+                            if ui
+                                .add_enabled(has_clipboard, egui::Button::new("📋 Paste here"))
+                                .clicked()
+                            {
+                                *on_paste_here = Some(target_dir.clone());
+                                ui.close_menu();
+                            }
+                            if ui
+                                .add_enabled(
+                                    has_clipboard,
+                                    egui::Button::new("🔗 Create symlink here"),
+                                )
+                                .on_hover_text("Link to the items in the buffer")
+                                .clicked()
+                            {
+                                *on_symlink_here = Some(target_dir);
                                 ui.close_menu();
                             }
-                        }
-                        let target_dir = if is_dir {
-                            path.clone()
-                        } else {
-                            path.parent().unwrap_or(cwd).to_path_buf()
-                        };
-                        if ui
-                            .add_enabled(has_clipboard, egui::Button::new("📋 Paste here"))
-                            .clicked()
-                        {
-                            *on_paste_here = Some(target_dir);
-                            ui.close_menu();
-                        }
 
-                        if ui.button("⟲ Undo last operation").clicked() {
-                            *on_undo_request = true;
-                            ui.close_menu();
-                        }
-                    });
+                            if ui
+                                .add_enabled(
+                                    undo_hint.is_some(),
+                                    egui::Button::new("⟲ Undo last operation"),
+                                )
+                                .on_hover_text(undo_hint.unwrap_or_default())
+                                .clicked()
+                            {
+                                *on_undo_request = true;
+                                ui.close_menu();
+                            }
+                            if ui
+                                .add_enabled(
+                                    redo_hint.is_some(),
+                                    egui::Button::new("⟳ Redo last undone operation"),
+                                )
+                                .on_hover_text(redo_hint.unwrap_or_default())
+                                .clicked()
+                            {
+                                *on_redo_request = true;
+                                ui.close_menu();
+                            }
+                        });
 
-                    if let Interaction::Rename {
-                        path: target,
-                        buffer,
-                    } = &mut self.interaction
-                    {
-                        if &path == target {
-                            let text_resp =
-                                ui.add(egui::TextEdit::singleline(buffer).desired_width(300.0));
-                            if text_resp.lost_focus() || ctx.input(|i| i.key_pressed(Key::Enter)) {
-                                *on_rename_request = Some((target.clone(), buffer.clone()));
-                                self.interaction = Interaction::None;
+                        if let Interaction::Rename {
+                            path: target,
+                            buffer,
+                        } = &mut self.interaction
+                        {
+                            if &path == target {
+                                let width = if grid { GRID_CELL } else { 300.0 };
+                                let text_resp =
+                                    ui.add(egui::TextEdit::singleline(buffer).desired_width(width));
+                                if details {
+                                    ui.end_row();
+                                }
+                                if ctx.input(|i| i.key_pressed(Key::Escape)) {
+                                    self.interaction = Interaction::None;
+                                } else if text_resp.lost_focus()
+                                    || ctx.input(|i| i.key_pressed(Key::Enter))
+                                {
+                                    *on_rename_request = Some((target.clone(), buffer.clone()));
+                                    self.interaction = Interaction::None;
+                                }
+                                return true;
                             }
-                            return true;
                         }
                     }
+                    false
+                };
+                if details {
+                    egui::Grid::new("entries-details")
+                        .num_columns(if cfg!(unix) { 5 } else { 4 })
+                        .striped(true)
+                        .show(ui, rows)
+                        .inner
+                } else if grid {
+                    ui.horizontal_wrapped(rows).inner
+                } else {
+                    rows(ui)
                 }
-                false
             })
             .inner;
 
         if !in_rename {
-            ctx.input(|i| {
-                if let Some(index) = self.selected {
-                    if index < self.entries.len() {
-                        let path = self.entries[index].path();
-                        if i.key_pressed(Key::Delete) {
-                            *on_delete_request = Some(path);
-                        } else if i.key_pressed(Key::F2) {
-                            let nm = path
-                                .file_name()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .to_string();
-                            self.interaction = Interaction::Rename { path, buffer: nm };
-                        }
-                    } else {
-                        self.selected = None;
-                    }
-                }
+            self.selected.retain(|&i| i < self.entries.len());
+            let (delete, rename, shift) = ctx.input(|i| {
+                (
+                    i.key_pressed(Key::Delete),
+                    i.key_pressed(Key::F2),
+                    i.modifiers.shift,
+                )
             });
+            if delete && !self.selected.is_empty() {
+                *on_delete_request = Some((self.selected_paths(), shift));
+            } else if rename
+                && self.selected.len() == 1
+                && let Some(path) = self.selected_paths().pop()
+            {
+                let nm = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+                self.interaction = Interaction::Rename { path, buffer: nm };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fs_ops::scratch;
+    use super::{FileBrowser, ListRequest, SortKey, natural_cmp};
+    use std::{cmp::Ordering, fs};
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        names.sort_by(|a, b| natural_cmp(a, b));
+        names
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        assert_eq!(
+            sorted(&["file10", "file2", "file1"]),
+            ["file1", "file2", "file10"]
+        );
+        assert_eq!(
+            sorted(&["a1b10", "a1b2", "a10b1", "a2b1"]),
+            ["a1b2", "a1b10", "a2b1", "a10b1"]
+        );
+        assert_eq!(natural_cmp("v2.10", "v2.9"), Ordering::Greater);
+        assert_eq!(natural_cmp("10", "9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn leading_zeros_do_not_change_the_value() {
+        assert_eq!(natural_cmp("file002", "file10"), Ordering::Less);
+        assert_eq!(natural_cmp("file010", "file9"), Ordering::Greater);
+        // Equal values still get a stable order.
+        assert_eq!(natural_cmp("file01", "file1"), Ordering::Less);
+        assert_eq!(natural_cmp("file1", "file01"), Ordering::Greater);
+    }
+
+    #[test]
+    fn letters_ignore_case_including_unicode() {
+        assert_eq!(sorted(&["b", "A", "a", "B"]), ["A", "a", "B", "b"]);
+        assert_eq!(natural_cmp("Éclair", "éclair2"), Ordering::Less);
+        assert_eq!(natural_cmp("straße10", "STRASSE"), Ordering::Greater);
+        assert_eq!(natural_cmp("Ωmega2", "ωmega10"), Ordering::Less);
+        assert_eq!(natural_cmp("日本2", "日本10"), Ordering::Less);
+    }
+
+    fn listed(dir: &std::path::Path, sort: (SortKey, bool)) -> Vec<String> {
+        let listing = FileBrowser::read_listing(&ListRequest {
+            cwd: dir.to_path_buf(),
+            show_hidden: true,
+            sort: Some(sort),
+            dirs_first: true,
+            no_ext_first: false,
+        });
+        listing
+            .entries
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn equal_sizes_list_in_name_order() {
+        let dir = scratch("equal-sizes");
+        for name in ["b10.txt", "a.txt", "b2.txt", "C.txt"] {
+            fs::write(dir.join(name), "abc").unwrap();
         }
+        fs::write(dir.join("big.txt"), "abcdef").unwrap();
+        assert_eq!(
+            listed(&dir, (SortKey::Size, false)),
+            ["a.txt", "b2.txt", "b10.txt", "C.txt", "big.txt"]
+        );
+        // Descending flips the sizes, not the names that break ties.
+        assert_eq!(
+            listed(&dir, (SortKey::Size, true)),
+            ["big.txt", "a.txt", "b2.txt", "b10.txt", "C.txt"]
+        );
+        fs::remove_dir_all(dir).unwrap();
     }
 }
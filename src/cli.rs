@@ -0,0 +1,120 @@
+use super::{fs_ops, searcher};
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+const SEARCH_USAGE: &str = "usage: rex search <root> <query> [--whole-word] [--hidden] [--gitignore] [--no-follow] [--glob | --regex] [--content | --name-and-content] [--ext <list>] [--min-size <size>] [--max-size <size>] [--within <days>] [--max-depth <n>]";
+
+// Reads the argument following an option such as `--min-size`.
+fn option_value<T>(
+    name: &str,
+    value: Option<&String>,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<T, i32> {
+    let Some(value) = value else {
+        eprintln!("rex: '{name}' needs a value");
+        eprintln!("{SEARCH_USAGE}");
+        return Err(2);
+    };
+    parse(value).ok_or_else(|| {
+        eprintln!("rex: invalid value '{value}' for '{name}'");
+        2
+    })
+}
+
+fn parse_extensions(list: &str) -> Option<HashSet<String>> {
+    Some(
+        list.split(',')
+            .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+            .filter(|e| !e.is_empty())
+            .collect(),
+    )
+}
+
+fn parse_search_args(args: &[String]) -> Result<(searcher::SearchOptions, Vec<String>), i32> {
+    let mut options = searcher::SearchOptions {
+        max_results: u64::MAX,
+        ..Default::default()
+    };
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--whole-word" => options.whole_word = true,
+            "--hidden" => options.skip_hidden = false,
+            "--gitignore" => options.respect_gitignore = true,
+            "--no-follow" => options.follow_symlinks = false,
+            "--glob" => options.mode = searcher::QueryMode::Glob,
+            "--regex" => options.mode = searcher::QueryMode::Regex,
+            "--content" => options.kind = searcher::SearchKind::Content,
+            "--name-and-content" => options.kind = searcher::SearchKind::NameAndContent,
+            "--ext" => options.extensions = option_value(arg, args.next(), parse_extensions)?,
+            "--min-size" => {
+                options.min_size = Some(option_value(arg, args.next(), fs_ops::parse_size)?);
+            }
+            "--max-size" => {
+                options.max_size = Some(option_value(arg, args.next(), fs_ops::parse_size)?);
+            }
+            "--within" => {
+                let days: u64 = option_value(arg, args.next(), |v| v.parse().ok())?;
+                options.modified_within = Some(Duration::from_secs(days * 24 * 60 * 60));
+            }
+            "--max-depth" => {
+                options.max_depth = Some(option_value(arg, args.next(), |v| v.parse().ok())?);
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("rex: unknown option '{arg}'");
+                eprintln!("{SEARCH_USAGE}");
+                return Err(2);
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+    Ok((options, positional))
+}
+
+pub fn run_search(args: &[String]) -> i32 {
+    let (mut options, positional) = match parse_search_args(args) {
+        Ok(parsed) => parsed,
+        Err(code) => return code,
+    };
+    let [root, query] = positional.as_slice() else {
+        eprintln!("{SEARCH_USAGE}");
+        return 2;
+    };
+
+    let root = PathBuf::from(root);
+    if !root.is_dir() {
+        eprintln!("rex: '{}' is not a directory", root.display());
+        return 1;
+    }
+
+    options.query = query.clone();
+    let abort = AtomicBool::new(false);
+    let out = std::io::stdout();
+    let searched = searcher::search(
+        &root,
+        &options,
+        &abort,
+        |r| {
+            // Stop walking once stdout is gone, e.g. when piped into `head`.
+            let written = match &r.line {
+                Some((n, text)) => writeln!(out.lock(), "{}:{n}: {text}", r.path.display()),
+                None => writeln!(out.lock(), "{}", r.path.display()),
+            };
+            if written.is_err() {
+                abort.store(true, Ordering::Relaxed);
+            }
+        },
+        |_| {},
+    );
+    if let Err(e) = searched {
+        eprintln!("rex: invalid pattern: {e}");
+        return 2;
+    }
+    0
+}
@@ -1,9 +1,11 @@
+use eframe::egui::Context;
 use std::path::PathBuf;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mode {
     Copy,
     Cut,
+    CopyStructure,
 }
 
 #[derive(Default, Clone)]
@@ -28,3 +30,7 @@ impl Clipboard {
         !self.items.is_empty() && self.mode.is_some()
     }
 }
+
+pub fn set_text(ctx: &Context, text: String) {
+    ctx.copy_text(text);
+}
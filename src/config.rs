@@ -1,4 +1,9 @@
-use std::path::PathBuf;
+use super::browser::{IconSet, SortKey, ViewKind};
+use super::searcher::{QueryMode, SearchOptions};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 pub fn os_root() -> PathBuf {
     #[cfg(target_os = "windows")]
@@ -46,6 +51,24 @@ pub fn prefs_path() -> PathBuf {
 pub fn trash_dir() -> PathBuf {
     data_dir().join("trash")
 }
+pub fn network_path() -> PathBuf {
+    data_dir().join("network.ini")
+}
+pub fn tags_path() -> PathBuf {
+    data_dir().join("tags.ini")
+}
+pub fn recent_dirs_path() -> PathBuf {
+    data_dir().join("recent.ini")
+}
+pub fn nav_history_path() -> PathBuf {
+    data_dir().join("nav_history.ini")
+}
+pub fn undo_history_path() -> PathBuf {
+    data_dir().join("undo.json")
+}
+pub fn search_history_path() -> PathBuf {
+    data_dir().join("search_history.ini")
+}
 
 pub fn load_pinned() -> Vec<PathBuf> {
     let path = pinned_path();
@@ -86,24 +109,556 @@ pub fn save_pinned(p: &[PathBuf]) {
     let _ = std::fs::write(path, content);
 }
 
-pub fn load_scale() -> f32 {
-    let path = prefs_path();
-    if let Ok(s) = std::fs::read_to_string(&path) {
-        for line in s.lines() {
-            if let Some(v) = line.strip_prefix("scale=") {
-                if let Ok(f) = v.trim().parse::<f32>() {
-                    return f.clamp(0.5, 3.0);
-                }
-            }
+pub fn load_network_shares() -> Vec<PathBuf> {
+    std::fs::read_to_string(network_path())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+pub fn save_network_shares(shares: &[PathBuf]) {
+    let path = network_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let content = shares
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let _ = std::fs::write(path, content);
+}
+
+pub fn load_search_history() -> Vec<String> {
+    std::fs::read_to_string(search_history_path())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+pub fn save_search_history(queries: &[String]) {
+    let path = search_history_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(path, queries.join("\n"));
+}
+
+pub fn load_tags() -> HashMap<PathBuf, String> {
+    std::fs::read_to_string(tags_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .filter(|(tag, _)| !tag.trim().is_empty())
+        .map(|(tag, path)| (PathBuf::from(path.trim()), tag.trim().to_string()))
+        .collect()
+}
+
+pub fn save_tags(tags: &HashMap<PathBuf, String>) {
+    let path = tags_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let content: String = tags
+        .iter()
+        .map(|(p, tag)| format!("{tag}\t{}\n", p.display()))
+        .collect();
+    let _ = std::fs::write(path, content);
+}
+
+pub fn load_recent_dirs() -> Vec<PathBuf> {
+    std::fs::read_to_string(recent_dirs_path())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+pub fn save_recent_dirs(dirs: &[PathBuf]) {
+    let path = recent_dirs_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let content = dirs
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let _ = std::fs::write(path, content);
+}
+
+/// Back and forward stacks, oldest first. Folders that are gone are dropped.
+pub fn load_nav_history() -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let (mut back, mut forward) = (vec![], vec![]);
+    for line in std::fs::read_to_string(nav_history_path())
+        .unwrap_or_default()
+        .lines()
+    {
+        let Some((stack, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let path = PathBuf::from(path);
+        if !path.is_dir() {
+            continue;
+        }
+        match stack {
+            "back" => back.push(path),
+            "forward" => forward.push(path),
+            _ => {}
         }
     }
-    1.0
+    (back, forward)
+}
+
+pub fn save_nav_history(back: &[PathBuf], forward: &[PathBuf]) {
+    let path = nav_history_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let content: String = back
+        .iter()
+        .map(|p| ("back", p))
+        .chain(forward.iter().map(|p| ("forward", p)))
+        .map(|(stack, p)| format!("{stack}\t{}\n", p.display()))
+        .collect();
+    let _ = std::fs::write(path, content);
 }
 
-pub fn save_scale(scale: f32) {
+// Blank lines, `#`/`;` comments and `[section]` headers hold no setting.
+fn pref_entry(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.starts_with(['#', ';', '[']) {
+        return None;
+    }
+    line.split_once('=').map(|(k, v)| (k.trim(), v.trim()))
+}
+
+fn read_prefs() -> Vec<(String, String)> {
+    std::fs::read_to_string(prefs_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(pref_entry)
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/// Writes `updates` into the prefs file. A key already there is changed on
+/// its own line and new keys go at the end; every other line, comments and
+/// section headers included, is kept as it was.
+fn update_prefs(updates: &[(&str, String)]) {
     let path = prefs_path();
     if let Some(parent) = path.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
-    let _ = std::fs::write(path, format!("scale={:.2}\n", scale.clamp(0.5, 3.0)));
+    let mut lines: Vec<String> = std::fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    for (key, value) in updates {
+        let line = format!("{key}={value}");
+        match lines
+            .iter_mut()
+            .find(|l| pref_entry(l).is_some_and(|(k, _)| k == *key))
+        {
+            Some(existing) => *existing = line,
+            None => lines.push(line),
+        }
+    }
+    let content: String = lines.iter().map(|l| format!("{l}\n")).collect();
+    let _ = std::fs::write(path, content);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::System => "Follow system",
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ResultClick {
+    Navigate,
+    NavigateKeepOpen,
+    Open,
+}
+
+impl ResultClick {
+    pub const ALL: [ResultClick; 3] = [
+        ResultClick::Navigate,
+        ResultClick::NavigateKeepOpen,
+        ResultClick::Open,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            ResultClick::Navigate => "navigate",
+            ResultClick::NavigateKeepOpen => "navigate_keep_open",
+            ResultClick::Open => "open",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResultClick::Navigate => "Go to folder",
+            ResultClick::NavigateKeepOpen => "Go to folder, keep results",
+            ResultClick::Open => "Open file",
+        }
+    }
+}
+
+/// Every setting kept in the prefs file, read in one pass and written back
+/// in one go. Keys this struct doesn't know about are left untouched on save.
+#[derive(Clone)]
+pub struct Prefs {
+    pub scale: f32,
+    pub scale_bounds: ScaleBounds,
+    pub theme: Theme,
+    pub show_hidden: bool,
+    pub sort_key: SortKey,
+    pub sort_desc: bool,
+    pub smart_sort: bool,
+    pub icon_set: IconSet,
+    pub view_kind: ViewKind,
+    pub show_preview: bool,
+    pub confirm_deletes: bool,
+    pub delete_permanently: bool,
+    /// Days a trashed item is kept before it is purged at startup; 0 keeps it forever.
+    pub trash_retention_days: u64,
+    pub low_space: LowSpace,
+    pub max_io_concurrency: usize,
+    pub search_mode: QueryMode,
+    pub max_content_bytes: u64,
+    pub search_max_results: u64,
+    pub result_click: ResultClick,
+    pub auto_refresh_secs: u64,
+    pub preserve_times: bool,
+    pub quiet_auto_rename: bool,
+    pub warn_extension_change: bool,
+}
+
+impl Default for Prefs {
+    fn default() -> Self {
+        let search = SearchOptions::default();
+        Self {
+            scale: 1.0,
+            scale_bounds: ScaleBounds::default(),
+            theme: Theme::System,
+            show_hidden: false,
+            sort_key: SortKey::Name,
+            sort_desc: false,
+            smart_sort: false,
+            icon_set: IconSet::Emoji,
+            view_kind: ViewKind::List,
+            show_preview: false,
+            confirm_deletes: true,
+            delete_permanently: false,
+            trash_retention_days: 0,
+            low_space: LowSpace::default(),
+            max_io_concurrency: default_io_concurrency(),
+            search_mode: QueryMode::default(),
+            max_content_bytes: search.max_content_bytes,
+            search_max_results: search.max_results,
+            result_click: ResultClick::Navigate,
+            auto_refresh_secs: 0,
+            preserve_times: true,
+            quiet_auto_rename: false,
+            warn_extension_change: false,
+        }
+    }
+}
+
+fn positive(v: &str) -> Option<f32> {
+    v.parse::<f32>().ok().filter(|f| f.is_finite() && *f > 0.0)
+}
+
+impl Prefs {
+    pub fn load() -> Self {
+        let mut prefs = Self::default();
+        for (k, v) in read_prefs() {
+            let v = v.as_str();
+            match k.as_str() {
+                "scale" => prefs.scale = positive(v).unwrap_or(prefs.scale),
+                "scale_min" => {
+                    prefs.scale_bounds.min = positive(v).unwrap_or(prefs.scale_bounds.min)
+                }
+                "scale_max" => {
+                    prefs.scale_bounds.max = positive(v).unwrap_or(prefs.scale_bounds.max)
+                }
+                "scale_step" => {
+                    prefs.scale_bounds.step = positive(v).unwrap_or(prefs.scale_bounds.step);
+                }
+                "theme" => prefs.theme = Theme::from_key(v).unwrap_or_default(),
+                "show_hidden" => prefs.show_hidden = v == "true",
+                "sort_key" => prefs.sort_key = SortKey::from_key(v).unwrap_or(SortKey::Name),
+                "sort_desc" => prefs.sort_desc = v == "true",
+                "smart_sort" => prefs.smart_sort = v == "true",
+                "icon_set" => prefs.icon_set = IconSet::from_key(v).unwrap_or(IconSet::Emoji),
+                "view_kind" => prefs.view_kind = ViewKind::from_key(v).unwrap_or(ViewKind::List),
+                "show_preview" => prefs.show_preview = v == "true",
+                "confirm_deletes" => prefs.confirm_deletes = v != "false",
+                "delete_permanently" => prefs.delete_permanently = v == "true",
+                "trash_retention_days" => {
+                    prefs.trash_retention_days = v.parse().unwrap_or(0);
+                }
+                "low_space_mb" => {
+                    if let Ok(mb) = v.parse() {
+                        prefs.low_space.min_mb = mb;
+                    }
+                }
+                "low_space_percent" => {
+                    if let Ok(percent) = v.parse()
+                        && percent <= 100
+                    {
+                        prefs.low_space.min_percent = percent;
+                    }
+                }
+                "max_io_concurrency" => {
+                    if let Ok(n) = v.parse()
+                        && n > 0
+                    {
+                        prefs.max_io_concurrency = n;
+                    }
+                }
+                "search_mode" => prefs.search_mode = QueryMode::from_key(v).unwrap_or_default(),
+                "content_search_max_mb" => {
+                    if let Ok(mb) = v.parse::<u64>() {
+                        prefs.max_content_bytes = mb * 1024 * 1024;
+                    }
+                }
+                "search_max_results" => {
+                    if let Ok(n) = v.parse()
+                        && n > 0
+                    {
+                        prefs.search_max_results = n;
+                    }
+                }
+                "result_click" => {
+                    prefs.result_click = ResultClick::from_key(v).unwrap_or(ResultClick::Navigate);
+                }
+                "auto_refresh_secs" => prefs.auto_refresh_secs = v.parse().unwrap_or(0),
+                "preserve_times" => prefs.preserve_times = v != "false",
+                "quiet_auto_rename" => prefs.quiet_auto_rename = v == "true",
+                "warn_extension_change" => prefs.warn_extension_change = v == "true",
+                _ => {}
+            }
+        }
+        if prefs.scale_bounds.min >= prefs.scale_bounds.max {
+            prefs.scale_bounds = ScaleBounds {
+                step: prefs.scale_bounds.step,
+                ..ScaleBounds::default()
+            };
+        }
+        prefs
+    }
+
+    pub fn save(&self) {
+        let bounds = &self.scale_bounds;
+        update_prefs(&[
+            ("scale", format!("{:.2}", self.scale)),
+            ("scale_min", format!("{:.2}", bounds.min)),
+            ("scale_max", format!("{:.2}", bounds.max)),
+            ("scale_step", format!("{:.2}", bounds.step)),
+            ("theme", self.theme.key().to_string()),
+            ("show_hidden", self.show_hidden.to_string()),
+            ("sort_key", self.sort_key.key().to_string()),
+            ("sort_desc", self.sort_desc.to_string()),
+            ("smart_sort", self.smart_sort.to_string()),
+            ("icon_set", self.icon_set.key().to_string()),
+            ("view_kind", self.view_kind.key().to_string()),
+            ("show_preview", self.show_preview.to_string()),
+            ("confirm_deletes", self.confirm_deletes.to_string()),
+            ("delete_permanently", self.delete_permanently.to_string()),
+            (
+                "trash_retention_days",
+                self.trash_retention_days.to_string(),
+            ),
+            ("low_space_mb", self.low_space.min_mb.to_string()),
+            ("low_space_percent", self.low_space.min_percent.to_string()),
+            ("max_io_concurrency", self.max_io_concurrency.to_string()),
+            ("search_mode", self.search_mode.key().to_string()),
+            (
+                "content_search_max_mb",
+                (self.max_content_bytes / (1024 * 1024)).to_string(),
+            ),
+            ("search_max_results", self.search_max_results.to_string()),
+            ("result_click", self.result_click.key().to_string()),
+            ("auto_refresh_secs", self.auto_refresh_secs.to_string()),
+            ("preserve_times", self.preserve_times.to_string()),
+            ("quiet_auto_rename", self.quiet_auto_rename.to_string()),
+            (
+                "warn_extension_change",
+                self.warn_extension_change.to_string(),
+            ),
+        ]);
+    }
+}
+
+pub struct SettingsBundle {
+    pub prefs: Vec<(String, String)>,
+    pub pinned: Vec<PathBuf>,
+}
+
+const BUNDLE_HEADER: &str = "# rex settings";
+
+pub fn default_bundle_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join("rex-settings.ini")
+}
+
+pub fn export_settings(path: &Path) -> std::io::Result<()> {
+    let mut content = format!("{BUNDLE_HEADER}\n[config]\n");
+    for (k, v) in read_prefs() {
+        content.push_str(&format!("{k}={v}\n"));
+    }
+    content.push_str("[pinned]\n");
+    for p in load_pinned() {
+        content.push_str(&format!("{}\n", p.display()));
+    }
+    std::fs::write(path, content)
+}
+
+pub fn read_bundle(path: &Path) -> Result<SettingsBundle, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    if lines.next() != Some(BUNDLE_HEADER) {
+        return Err("Not a REX settings file".into());
+    }
+    let mut bundle = SettingsBundle {
+        prefs: vec![],
+        pinned: vec![],
+    };
+    let mut section = "";
+    for (n, line) in lines.enumerate() {
+        match line {
+            "[config]" | "[pinned]" => section = line,
+            _ if line.starts_with('[') => return Err(format!("Unknown section {line}")),
+            _ if section == "[config]" => match line.split_once('=') {
+                Some((k, v)) if !k.trim().is_empty() => bundle
+                    .prefs
+                    .push((k.trim().to_string(), v.trim().to_string())),
+                _ => return Err(format!("Malformed setting on line {}: {line}", n + 2)),
+            },
+            _ if section == "[pinned]" => bundle.pinned.push(PathBuf::from(line)),
+            _ => return Err(format!("Line {} is outside any section", n + 2)),
+        }
+    }
+    Ok(bundle)
+}
+
+pub fn apply_bundle(bundle: &SettingsBundle, replace: bool) {
+    if replace {
+        let path = prefs_path();
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let content: String = bundle
+            .prefs
+            .iter()
+            .map(|(k, v)| format!("{k}={v}\n"))
+            .collect();
+        let _ = std::fs::write(path, content);
+        save_pinned(&bundle.pinned);
+    } else {
+        let updates: Vec<(&str, String)> = bundle
+            .prefs
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+        update_prefs(&updates);
+        let mut pinned = load_pinned();
+        for p in &bundle.pinned {
+            if !pinned.contains(p) {
+                pinned.push(p.clone());
+            }
+        }
+        save_pinned(&pinned);
+    }
+}
+
+pub fn default_io_concurrency() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+#[derive(Clone, Copy)]
+pub struct LowSpace {
+    pub min_mb: u64,
+    pub min_percent: u64,
+}
+
+impl LowSpace {
+    pub fn is_low(&self, available: u64, total: u64) -> bool {
+        available < self.min_mb * 1024 * 1024
+            || (total > 0 && available.saturating_mul(100) < total.saturating_mul(self.min_percent))
+    }
+}
+
+impl Default for LowSpace {
+    fn default() -> Self {
+        Self {
+            min_mb: 1024,
+            min_percent: 5,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ScaleBounds {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl Default for ScaleBounds {
+    fn default() -> Self {
+        Self {
+            min: 0.5,
+            max: 3.0,
+            step: 0.1,
+        }
+    }
+}
+
+impl ScaleBounds {
+    pub fn clamp(&self, scale: f32) -> f32 {
+        scale.clamp(self.min, self.max)
+    }
 }
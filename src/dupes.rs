@@ -0,0 +1,108 @@
+use super::fs_ops::{self, HashKind};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+};
+
+#[derive(Clone)]
+pub struct DupeGroup {
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+pub enum DupeMsg {
+    Scanned(u64),
+    Hashed { done: u64, total: u64 },
+    Done(Vec<DupeGroup>),
+}
+
+fn scan(dir: &Path, abort: &AtomicBool, by_size: &mut HashMap<u64, Vec<PathBuf>>) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut files = 0;
+    for e in entries.flatten() {
+        if abort.load(Ordering::Relaxed) {
+            break;
+        }
+        // Symlinks are skipped: they would only ever duplicate their target.
+        let Ok(ft) = e.file_type() else {
+            continue;
+        };
+        if ft.is_dir() {
+            files += scan(&e.path(), abort, by_size);
+        } else if ft.is_file()
+            && let Ok(meta) = e.metadata()
+            && meta.len() > 0
+        {
+            by_size.entry(meta.len()).or_default().push(e.path());
+            files += 1;
+        }
+    }
+    files
+}
+
+/// Groups identical files below `root`. Only files that share a size with
+/// another file are hashed. Returns `None` if aborted.
+pub fn find_duplicates(
+    root: &Path,
+    abort: &AtomicBool,
+    mut on_progress: impl FnMut(DupeMsg),
+) -> Option<Vec<DupeGroup>> {
+    let mut by_size = HashMap::new();
+    let scanned = scan(root, abort, &mut by_size);
+    on_progress(DupeMsg::Scanned(scanned));
+
+    let candidates: Vec<(u64, Vec<PathBuf>)> = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect();
+    let total = candidates.iter().map(|(_, p)| p.len() as u64).sum();
+    let mut done = 0;
+    let mut groups = Vec::new();
+    for (size, paths) in candidates {
+        let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for p in paths {
+            if abort.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Ok(digest) = fs_ops::hash_file(&p, HashKind::Sha256, abort) {
+                by_hash.entry(digest).or_default().push(p);
+            }
+            done += 1;
+            on_progress(DupeMsg::Hashed { done, total });
+        }
+        groups.extend(
+            by_hash
+                .into_values()
+                .filter(|paths| paths.len() > 1)
+                .map(|mut paths| {
+                    paths.sort();
+                    DupeGroup { size, paths }
+                }),
+        );
+    }
+    // Biggest savings first.
+    groups.sort_by(|a, b| {
+        let waste = |g: &DupeGroup| g.size * (g.paths.len() as u64 - 1);
+        waste(b).cmp(&waste(a))
+    });
+    (!abort.load(Ordering::Relaxed)).then_some(groups)
+}
+
+pub fn spawn_find(root: PathBuf, tx: Sender<DupeMsg>, abort: Arc<AtomicBool>) {
+    std::thread::spawn(move || {
+        let progress = tx.clone();
+        if let Some(groups) = find_duplicates(&root, &abort, |msg| {
+            let _ = progress.send(msg);
+        }) {
+            let _ = tx.send(DupeMsg::Done(groups));
+        }
+    });
+}
@@ -1,45 +1,411 @@
 use super::config;
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
 use std::{
     fs,
+    io::{self, Read, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Op {
-    Rename { from: PathBuf, to: PathBuf },
-    Move { from: PathBuf, to: PathBuf },
-    Copy { to: PathBuf },
-    Delete { trashed: PathBuf, original: PathBuf },
-    MkDir { path: PathBuf },
-    Touch { path: PathBuf },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    Copy {
+        from: PathBuf,
+        to: PathBuf,
+        #[serde(default)]
+        stamp: Option<Stamp>,
+    },
+    CopyStructure {
+        from: PathBuf,
+        to: PathBuf,
+        #[serde(default)]
+        stamp: Option<Stamp>,
+    },
+    Delete {
+        trashed: PathBuf,
+        original: PathBuf,
+    },
+    Trash {
+        original: PathBuf,
+    },
+    MkDir {
+        path: PathBuf,
+        #[serde(default)]
+        stamp: Option<Stamp>,
+    },
+    Symlink {
+        target: PathBuf,
+        link: PathBuf,
+    },
+    Extract {
+        archive: PathBuf,
+        to: PathBuf,
+        #[serde(default)]
+        stamp: Option<Stamp>,
+    },
+    Compress {
+        sources: Vec<PathBuf>,
+        to: PathBuf,
+        #[serde(default)]
+        stamp: Option<Stamp>,
+    },
+    Touch {
+        path: PathBuf,
+        #[serde(default)]
+        stamp: Option<Stamp>,
+    },
+    RmDir {
+        path: PathBuf,
+    },
+    SetMtime {
+        path: PathBuf,
+        old: SystemTime,
+        new: SystemTime,
+    },
+    Chmod {
+        path: PathBuf,
+        old_mode: u32,
+        new_mode: u32,
+    },
+    Batch {
+        ops: Vec<Op>,
+    },
 }
 
-fn copy_rec(from: &Path, to: &Path) -> std::io::Result<()> {
-    if from.is_dir() {
+/// What an item looked like right after an operation created it. Undo only
+/// takes the item away again while it still looks the same.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stamp {
+    File {
+        len: u64,
+        modified: Option<SystemTime>,
+    },
+    Dir {
+        entries: usize,
+    },
+}
+
+impl Stamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let meta = fs::symlink_metadata(path).ok()?;
+        if meta.is_dir() {
+            Some(Stamp::Dir {
+                entries: fs::read_dir(path).ok()?.count(),
+            })
+        } else {
+            Some(Stamp::File {
+                len: meta.len(),
+                modified: meta.modified().ok(),
+            })
+        }
+    }
+}
+
+fn unchanged(path: &Path, stamp: &Option<Stamp>) -> bool {
+    stamp.is_some() && Stamp::of(path) == *stamp
+}
+
+/// Whether two paths name the same entry, e.g. `a.txt` and `A.txt` on a
+/// case-insensitive filesystem.
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+// Windows filesystems ignore case.
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    let lower = |p: &Path| p.file_name().map(|n| n.to_string_lossy().to_lowercase());
+    a.parent() == b.parent() && lower(a) == lower(b) && a.exists()
+}
+
+fn in_the_way(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} is in the way", path.display()),
+    )
+}
+
+impl Op {
+    pub fn subject(&self) -> Option<&Path> {
+        match self {
+            Op::Rename { to, .. }
+            | Op::Move { to, .. }
+            | Op::Copy { to, .. }
+            | Op::CopyStructure { to, .. }
+            | Op::Extract { to, .. }
+            | Op::Compress { to, .. } => Some(to),
+            Op::Delete { original, .. } | Op::Trash { original } => Some(original),
+            Op::Symlink { link, .. } => Some(link),
+            Op::MkDir { path, .. }
+            | Op::Touch { path, .. }
+            | Op::RmDir { path }
+            | Op::SetMtime { path, .. }
+            | Op::Chmod { path, .. } => Some(path),
+            Op::Batch { .. } => None,
+        }
+    }
+
+    /// One-line summary for menus and the history list.
+    pub fn describe(&self) -> String {
+        let name = |p: &Path| {
+            p.file_name()
+                .unwrap_or(p.as_os_str())
+                .to_string_lossy()
+                .to_string()
+        };
+        let dir = |p: &Path| p.parent().unwrap_or(p).display().to_string();
+        match self {
+            Op::Rename { from, to } => format!("Rename {} → {}", name(from), name(to)),
+            Op::Move { from, to } => format!("Move {} → {}", name(from), dir(to)),
+            Op::Copy { from, to, .. } => format!("Copy {} → {}", name(from), dir(to)),
+            Op::CopyStructure { from, to, .. } => {
+                format!("Copy structure of {} → {}", name(from), dir(to))
+            }
+            Op::Delete { original, .. } | Op::Trash { original } => {
+                format!("Trash {}", name(original))
+            }
+            Op::Symlink { target, link } => format!("Link {} → {}", name(link), target.display()),
+            Op::Extract { archive, .. } => format!("Extract {}", name(archive)),
+            Op::Compress { sources, to, .. } => {
+                format!("Compress {} item(s) into {}", sources.len(), name(to))
+            }
+            Op::MkDir { path, .. } => format!("New folder {}", name(path)),
+            Op::Touch { path, .. } => format!("New file {}", name(path)),
+            Op::RmDir { path } => format!("Remove folder {}", name(path)),
+            Op::SetMtime { path, .. } => format!("Change modified date of {}", name(path)),
+            Op::Chmod { path, new_mode, .. } => {
+                format!("Change mode of {} to {new_mode:o}", name(path))
+            }
+            // Batches are an item plus whatever it displaced; the item is what
+            // the user did.
+            Op::Batch { ops } => match ops.last() {
+                Some(op) if ops.len() > 1 => {
+                    format!("{} (+{} more)", op.describe(), ops.len() - 1)
+                }
+                Some(op) => op.describe(),
+                None => "Nothing".to_string(),
+            },
+        }
+    }
+
+    /// Whether undoing still makes sense: the item undo acts on is there,
+    /// unchanged if undo would take it away, and nothing is in the way of
+    /// putting it back.
+    pub fn can_undo(&self) -> bool {
+        match self {
+            Op::Rename { from, to } | Op::Move { from, to } => {
+                to.exists() && (!from.exists() || same_file(from, to))
+            }
+            Op::Copy { to, stamp, .. }
+            | Op::CopyStructure { to, stamp, .. }
+            | Op::Extract { to, stamp, .. }
+            | Op::Compress { to, stamp, .. } => unchanged(to, stamp),
+            Op::Delete { trashed, original } => trashed.exists() && !original.exists(),
+            Op::Trash { original } => !original.exists(),
+            Op::Symlink { link, .. } => link.is_symlink(),
+            Op::MkDir { path, stamp } | Op::Touch { path, stamp } => unchanged(path, stamp),
+            Op::SetMtime { path, .. } | Op::Chmod { path, .. } => path.exists(),
+            Op::RmDir { path } => !path.exists(),
+            Op::Batch { ops } => {
+                // Steps are undone last to first, so each is checked against
+                // what the later ones leave behind. An overwrite's displaced
+                // item goes back to a path the new item only vacates once its
+                // own step has been undone.
+                let mut vacated = Vec::new();
+                ops.iter().rev().all(|op| {
+                    let ok = match op {
+                        Op::Delete { trashed, original }
+                            if vacated.contains(&original.as_path()) =>
+                        {
+                            trashed.exists()
+                        }
+                        op => op.can_undo(),
+                    };
+                    vacated.extend(op.vacates());
+                    ok
+                })
+            }
+        }
+    }
+
+    /// The path undoing this op takes away, if any.
+    fn vacates(&self) -> Option<&Path> {
+        match self {
+            Op::Rename { to, .. }
+            | Op::Move { to, .. }
+            | Op::Copy { to, .. }
+            | Op::CopyStructure { to, .. }
+            | Op::Extract { to, .. }
+            | Op::Compress { to, .. }
+            | Op::MkDir { path: to, .. }
+            | Op::Touch { path: to, .. }
+            | Op::Symlink { link: to, .. } => Some(to),
+            _ => None,
+        }
+    }
+
+    fn restamped(&self) -> Op {
+        let mut op = self.clone();
+        match &mut op {
+            Op::Copy { to, stamp, .. }
+            | Op::CopyStructure { to, stamp, .. }
+            | Op::Extract { to, stamp, .. }
+            | Op::Compress { to, stamp, .. }
+            | Op::MkDir { path: to, stamp }
+            | Op::Touch { path: to, stamp } => *stamp = Stamp::of(to),
+            Op::Batch { ops } => *ops = ops.iter().map(Op::restamped).collect(),
+            _ => {}
+        }
+        op
+    }
+
+    pub fn auto_renamed(&self) -> Option<(String, String)> {
+        match self {
+            Op::Copy { from, to, .. } | Op::Move { from, to }
+                if from.file_name() != to.file_name() =>
+            {
+                let name = |p: &Path| {
+                    p.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string()
+                };
+                Some((name(from), name(to)))
+            }
+            _ => None,
+        }
+    }
+}
+
+const COPY_CHUNK: usize = 1024 * 1024;
+
+/// Called after each chunk with the file being copied and the bytes just
+/// written; returning false cancels the copy.
+pub type Progress<'a> = &'a mut dyn FnMut(&Path, u64) -> bool;
+
+fn no_progress(_: &Path, _: u64) -> bool {
+    true
+}
+
+fn copy_times(meta: &fs::Metadata, to: &Path) -> std::io::Result<()> {
+    filetime::set_file_times(
+        to,
+        FileTime::from_last_access_time(meta),
+        FileTime::from_last_modification_time(meta),
+    )
+}
+
+fn copy_file(
+    from: &Path,
+    to: &Path,
+    preserve_times: bool,
+    progress: Progress,
+) -> std::io::Result<()> {
+    let mut reader = fs::File::open(from)?;
+    let mut writer = fs::File::create(to)?;
+    let mut buf = vec![0; COPY_CHUNK];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        if !progress(from, n as u64) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+    }
+    drop(writer);
+    let meta = reader.metadata()?;
+    fs::set_permissions(to, meta.permissions())?;
+    // Best effort: some filesystems refuse to set times.
+    if preserve_times {
+        let _ = copy_times(&meta, to);
+    }
+    Ok(())
+}
+
+fn copy_rec(
+    from: &Path,
+    to: &Path,
+    preserve_times: bool,
+    progress: Progress,
+) -> std::io::Result<()> {
+    if from.is_symlink() {
+        if let Some(p) = to.parent() {
+            fs::create_dir_all(p)?;
+        }
+        make_link(&fs::read_link(from)?, to)?;
+    } else if from.is_dir() {
         fs::create_dir_all(to)?;
         for e in fs::read_dir(from)? {
             let e = e?;
             let src = e.path();
             let dst = to.join(e.file_name());
-            copy_rec(&src, &dst)?;
+            copy_rec(&src, &dst, preserve_times, progress)?;
+        }
+        // Last, since filling the folder bumps its modified time. Best effort:
+        // some filesystems refuse times on directories.
+        if preserve_times && let Ok(meta) = fs::metadata(from) {
+            let _ = copy_times(&meta, to);
         }
     } else {
         if let Some(p) = to.parent() {
             fs::create_dir_all(p)?;
         }
-        fs::copy(from, to)?;
+        copy_file(from, to, preserve_times, progress)?;
+    }
+    Ok(())
+}
+
+fn mkdir_tree(from: &Path, to: &Path, preserve_times: bool) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for e in fs::read_dir(from)? {
+        let e = e?;
+        let src = e.path();
+        if src.is_dir() {
+            mkdir_tree(&src, &to.join(e.file_name()), preserve_times)?;
+        }
+    }
+    if preserve_times && let Ok(meta) = fs::metadata(from) {
+        let _ = copy_times(&meta, to);
     }
     Ok(())
 }
 
 fn move_rec(from: &Path, to: &Path) -> std::io::Result<()> {
+    move_rec_with(from, to, &mut no_progress)
+}
+
+fn move_rec_with(from: &Path, to: &Path, progress: Progress) -> std::io::Result<()> {
     if let Some(p) = to.parent() {
         fs::create_dir_all(p)?;
     }
     match fs::rename(from, to) {
         Ok(()) => Ok(()),
         Err(_) => {
-            copy_rec(from, to)?;
+            // Leave the source untouched if the copy doesn't finish.
+            // A move keeps its timestamps, even when it has to copy.
+            if let Err(e) = copy_rec(from, to, true, progress) {
+                let _ = remove_rec(to);
+                return Err(e);
+            }
             remove_rec(from)
         }
     }
@@ -69,38 +435,393 @@ fn unique_in(dir: &Path, name: &str) -> PathBuf {
     }
 }
 
-pub fn copy(from: &Path, to_dir: &Path) -> std::io::Result<Op> {
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Conflict {
+    KeepBoth,
+    Overwrite,
+}
+
+pub fn collides(from: &Path, to_dir: &Path) -> bool {
+    let dst = to_dir.join(from.file_name().unwrap_or_default());
+    dst.exists() && dst != from
+}
+
+fn destination(
+    from: &Path,
+    to_dir: &Path,
+    conflict: Conflict,
+) -> std::io::Result<(PathBuf, Option<Op>)> {
     let name = from.file_name().unwrap_or_default().to_string_lossy();
-    let dst = unique_in(to_dir, &name);
-    copy_rec(from, &dst)?;
-    Ok(Op::Copy {
-        to: dst,
-    })
+    let dst = to_dir.join(&*name);
+    if conflict == Conflict::Overwrite && collides(from, to_dir) {
+        // The displaced entry goes to the trash so undo can bring it back.
+        let displaced = move_to_app_trash(&dst)?;
+        return Ok((dst, Some(displaced)));
+    }
+    Ok((unique_in(to_dir, &name), None))
+}
+
+fn with_displaced(displaced: Option<Op>, op: Op) -> Op {
+    match displaced {
+        Some(d) => Op::Batch { ops: vec![d, op] },
+        None => op,
+    }
+}
+
+fn restore_displaced(displaced: &Option<Op>) {
+    if let Some(op) = displaced {
+        let _ = undo(op);
+    }
+}
+
+/// True if `to_dir` is the folder `from` or somewhere below it. A symlink
+/// never counts, since only the link itself is copied or moved.
+pub fn is_inside(from: &Path, to_dir: &Path) -> bool {
+    if !fs::symlink_metadata(from).is_ok_and(|m| m.is_dir()) {
+        return false;
+    }
+    match (fs::canonicalize(from), fs::canonicalize(to_dir)) {
+        (Ok(from), Ok(to_dir)) => to_dir.starts_with(from),
+        _ => false,
+    }
+}
+
+fn guard_not_inside(from: &Path, to_dir: &Path) -> std::io::Result<()> {
+    if is_inside(from, to_dir) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot put a folder inside itself",
+        ));
+    }
+    Ok(())
+}
+
+pub fn copy(
+    from: &Path,
+    to_dir: &Path,
+    conflict: Conflict,
+    preserve_times: bool,
+    progress: Progress,
+) -> std::io::Result<Op> {
+    guard_not_inside(from, to_dir)?;
+    let (dst, displaced) = destination(from, to_dir, conflict)?;
+    if let Err(e) = copy_rec(from, &dst, preserve_times, progress) {
+        let _ = remove_rec(&dst);
+        restore_displaced(&displaced);
+        return Err(e);
+    }
+    Ok(with_displaced(
+        displaced,
+        Op::Copy {
+            from: from.to_path_buf(),
+            stamp: Stamp::of(&dst),
+            to: dst,
+        },
+    ))
 }
 
-pub fn mv(from: &Path, to_dir: &Path) -> std::io::Result<Op> {
+pub fn copy_tree_structure(
+    from: &Path,
+    to_dir: &Path,
+    preserve_times: bool,
+) -> std::io::Result<Op> {
+    if !from.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only folders have a structure to copy",
+        ));
+    }
+    guard_not_inside(from, to_dir)?;
     let name = from.file_name().unwrap_or_default().to_string_lossy();
     let dst = unique_in(to_dir, &name);
-    move_rec(from, &dst)?;
-    Ok(Op::Move {
+    mkdir_tree(from, &dst, preserve_times)?;
+    Ok(Op::CopyStructure {
         from: from.to_path_buf(),
+        stamp: Stamp::of(&dst),
         to: dst,
     })
 }
 
-pub fn rename(from: &Path, new_name: &str) -> std::io::Result<Op> {
+pub fn mv(
+    from: &Path,
+    to_dir: &Path,
+    conflict: Conflict,
+    progress: Progress,
+) -> std::io::Result<Op> {
+    guard_not_inside(from, to_dir)?;
+    let (dst, displaced) = destination(from, to_dir, conflict)?;
+    if let Err(e) = move_rec_with(from, &dst, progress) {
+        restore_displaced(&displaced);
+        return Err(e);
+    }
+    Ok(with_displaced(
+        displaced,
+        Op::Move {
+            from: from.to_path_buf(),
+            to: dst,
+        },
+    ))
+}
+
+const RESERVED_WINDOWS_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+pub fn check_name(name: &str) -> io::Result<()> {
+    let reason = if name.trim().is_empty() {
+        "name cannot be empty".to_string()
+    } else if name == "." || name == ".." {
+        format!("\"{name}\" is not a valid name")
+    } else if name.chars().any(std::path::is_separator) {
+        "name cannot contain path separators".to_string()
+    } else if name.contains('\0') {
+        "name cannot contain NUL characters".to_string()
+    } else if !cfg!(target_os = "windows") {
+        return Ok(());
+    } else if let Some(c) = name
+        .chars()
+        .find(|c| "<>:\"|?*".contains(*c) || c.is_control())
+    {
+        format!("name cannot contain {c:?}")
+    } else if name.ends_with(['.', ' ']) {
+        "name cannot end with a dot or a space".to_string()
+    } else {
+        let stem = name.split('.').next().unwrap_or_default().trim_end();
+        if !RESERVED_WINDOWS_NAMES.contains(&stem.to_uppercase().as_str()) {
+            return Ok(());
+        }
+        format!("\"{stem}\" is a reserved name on Windows")
+    };
+    Err(io::Error::new(io::ErrorKind::InvalidInput, reason))
+}
+
+pub fn rename(from: &Path, new_name: &str, overwrite: bool) -> std::io::Result<Op> {
+    check_name(new_name)?;
     let to = from.with_file_name(new_name);
-    move_rec(from, &to)?;
-    Ok(Op::Rename {
-        from: from.to_path_buf(),
-        to,
-    })
+    let mut displaced = None;
+    // A case-only rename resolves to the same file on case-insensitive filesystems.
+    if to.exists() && !same_file(from, &to) {
+        if !overwrite {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("\"{new_name}\" already exists"),
+            ));
+        }
+        displaced = Some(move_to_app_trash(&to)?);
+    }
+    fs::rename(from, &to)?;
+    Ok(with_displaced(
+        displaced,
+        Op::Rename {
+            from: from.to_path_buf(),
+            to,
+        },
+    ))
 }
 
 pub fn mkdir(where_: &Path, name: &str) -> std::io::Result<Op> {
     let dst = where_.join(name);
     std::fs::create_dir_all(&dst)?;
-    Ok(Op::MkDir { path: dst })
+    Ok(Op::MkDir {
+        stamp: Stamp::of(&dst),
+        path: dst,
+    })
+}
+
+#[cfg(unix)]
+fn make_link(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn make_link(target: &Path, link: &Path) -> std::io::Result<()> {
+    if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn make_link(_target: &Path, _link: &Path) -> std::io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symbolic links are not supported on this platform",
+    ))
+}
+
+fn remove_link(link: &Path) -> std::io::Result<()> {
+    // Directory links on Windows are removed like directories.
+    fs::remove_file(link).or_else(|e| fs::remove_dir(link).map_err(|_| e))
+}
+
+pub fn symlink(target: &Path, where_: &Path) -> std::io::Result<Op> {
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    let link = unique_in(where_, &name);
+    make_link(target, &link)?;
+    Ok(Op::Symlink {
+        target: target.to_path_buf(),
+        link,
+    })
+}
+
+pub fn is_zip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+fn unzip_into(
+    archive: &Path,
+    dst: &Path,
+    progress: &mut dyn FnMut(u64, u64, &Path) -> bool,
+) -> std::io::Result<()> {
+    let mut zip = zip::ZipArchive::new(fs::File::open(archive)?).map_err(io::Error::other)?;
+    let total = zip.len() as u64;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(io::Error::other)?;
+        // Reject entries like "../../x" or absolute paths (zip slip).
+        let out = entry
+            .enclosed_name()
+            .map(|rel| dst.join(rel))
+            .filter(|out| out.starts_with(dst))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unsafe entry path: {}", entry.name()),
+                )
+            })?;
+        if !progress(i as u64, total, &out) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        if entry.is_dir() {
+            fs::create_dir_all(&out)?;
+            continue;
+        }
+        if let Some(p) = out.parent() {
+            fs::create_dir_all(p)?;
+        }
+        io::copy(&mut entry, &mut fs::File::create(&out)?)?;
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&out, fs::Permissions::from_mode(mode))?;
+        }
+    }
+    progress(total, total, dst);
+    Ok(())
+}
+
+/// Unpacks a zip archive into a new folder next to it, named after the archive.
+pub fn extract_zip(
+    archive: &Path,
+    progress: &mut dyn FnMut(u64, u64, &Path) -> bool,
+) -> std::io::Result<Op> {
+    let parent = archive.parent().unwrap_or(Path::new("."));
+    let stem = archive.file_stem().unwrap_or_default().to_string_lossy();
+    let dst = unique_in(parent, &stem);
+    fs::create_dir_all(&dst)?;
+    if let Err(e) = unzip_into(archive, &dst, progress) {
+        let _ = remove_rec(&dst);
+        return Err(e);
+    }
+    Ok(Op::Extract {
+        archive: archive.to_path_buf(),
+        stamp: Stamp::of(&dst),
+        to: dst,
+    })
+}
+
+/// `<first item>.zip` next to the first item, numbered if that name is taken.
+pub fn default_zip_path(first: &Path) -> PathBuf {
+    let dir = first.parent().unwrap_or(Path::new("."));
+    let name = first.file_name().unwrap_or_default().to_string_lossy();
+    let mut cand = dir.join(format!("{name}.zip"));
+    let mut idx = 1usize;
+    while cand.exists() {
+        cand = dir.join(format!("{name} ({idx}).zip"));
+        idx += 1;
+    }
+    cand
+}
+
+fn zip_entries(p: &Path, name: String, out: &mut Vec<(PathBuf, String)>) -> std::io::Result<()> {
+    if p.is_symlink() && p.is_dir() {
+        // Linked folders are left out rather than risk following a cycle.
+        return Ok(());
+    }
+    if p.is_dir() {
+        out.push((p.to_path_buf(), format!("{name}/")));
+        for e in fs::read_dir(p)? {
+            let e = e?;
+            let child = format!("{name}/{}", e.file_name().to_string_lossy());
+            zip_entries(&e.path(), child, out)?;
+        }
+    } else {
+        out.push((p.to_path_buf(), name));
+    }
+    Ok(())
+}
+
+fn write_zip(
+    paths: &[PathBuf],
+    out: &Path,
+    progress: &mut dyn FnMut(u64, u64, &Path) -> bool,
+) -> std::io::Result<()> {
+    // Entries are named relative to each item's parent, so "a/b.txt" stays "a/b.txt".
+    let mut entries = Vec::new();
+    for p in paths {
+        let name = p
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        zip_entries(p, name, &mut entries)?;
+    }
+    let mut zip = zip::ZipWriter::new(fs::File::create_new(out)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let total = entries.len() as u64;
+    for (i, (path, name)) in entries.iter().enumerate() {
+        if !progress(i as u64, total, path) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        #[cfg(unix)]
+        let options = {
+            use std::os::unix::fs::PermissionsExt;
+            options.unix_permissions(fs::metadata(path)?.permissions().mode())
+        };
+        if name.ends_with('/') {
+            zip.add_directory(name.as_str(), options)
+                .map_err(io::Error::other)?;
+        } else {
+            zip.start_file(name.as_str(), options)
+                .map_err(io::Error::other)?;
+            io::copy(&mut fs::File::open(path)?, &mut zip)?;
+        }
+    }
+    zip.finish().map_err(io::Error::other)?;
+    progress(total, total, out);
+    Ok(())
+}
+
+pub fn zip_to(
+    paths: &[PathBuf],
+    out: &Path,
+    progress: &mut dyn FnMut(u64, u64, &Path) -> bool,
+) -> std::io::Result<Op> {
+    if let Err(e) = write_zip(paths, out, progress) {
+        // Only clean up a file this call created.
+        if e.kind() != io::ErrorKind::AlreadyExists {
+            let _ = fs::remove_file(out);
+        }
+        return Err(e);
+    }
+    Ok(Op::Compress {
+        sources: paths.to_vec(),
+        to: out.to_path_buf(),
+        stamp: Stamp::of(out),
+    })
 }
 
 pub fn touch(where_: &Path, name: &str) -> std::io::Result<Op> {
@@ -112,10 +833,72 @@ pub fn touch(where_: &Path, name: &str) -> std::io::Result<Op> {
         .create(true)
         .write(true)
         .open(&dst)?;
-    Ok(Op::Touch { path: dst })
+    Ok(Op::Touch {
+        stamp: Stamp::of(&dst),
+        path: dst,
+    })
+}
+
+fn trash_error(e: trash::Error) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+#[cfg(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn restore_from_os_trash(original: &Path) -> std::io::Result<()> {
+    // The same path may have been trashed more than once; bring back the latest.
+    let item = trash::os_limited::list()
+        .map_err(trash_error)?
+        .into_iter()
+        .filter(|item| item.original_path() == original)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is no longer in the trash", original.display()),
+            )
+        })?;
+    trash::os_limited::restore_all([item]).map_err(trash_error)
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn restore_from_os_trash(original: &Path) -> std::io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!(
+            "Restore {} from the system trash instead",
+            original.display()
+        ),
+    ))
 }
 
 pub fn delete_to_trash(p: &Path) -> std::io::Result<Op> {
+    if trash::delete(p).is_ok() {
+        return Ok(Op::Trash {
+            original: p.to_path_buf(),
+        });
+    }
+    move_to_app_trash(p)
+}
+
+/// Moves `p` into rex's own trash folder, which undo can always restore from,
+/// unlike the system trash on some platforms.
+fn move_to_app_trash(p: &Path) -> std::io::Result<Op> {
     let trash = config::trash_dir();
     std::fs::create_dir_all(&trash)?;
     let name = p.file_name().unwrap_or_default().to_string_lossy();
@@ -127,12 +910,640 @@ pub fn delete_to_trash(p: &Path) -> std::io::Result<Op> {
     })
 }
 
+fn collect_nested(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    dirs: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    for e in fs::read_dir(dir)? {
+        let e = e?;
+        if e.file_type()?.is_dir() {
+            collect_nested(&e.path(), files, dirs)?;
+            dirs.push(e.path());
+        } else {
+            files.push(e.path());
+        }
+    }
+    Ok(())
+}
+
+fn flatten_into(dir: &Path, remove_empty: bool, ops: &mut Vec<Op>) -> std::io::Result<()> {
+    let mut files = vec![];
+    let mut subdirs = vec![];
+    for e in fs::read_dir(dir)? {
+        let e = e?;
+        if e.file_type()?.is_dir() {
+            collect_nested(&e.path(), &mut files, &mut subdirs)?;
+            subdirs.push(e.path());
+        }
+    }
+    for from in files {
+        let name = from.file_name().unwrap_or_default().to_string_lossy();
+        let to = unique_in(dir, &name);
+        move_rec(&from, &to)?;
+        ops.push(Op::Move { from, to });
+    }
+    if remove_empty {
+        // Deepest folders come first, and anything still holding entries is left alone.
+        for path in subdirs {
+            if fs::remove_dir(&path).is_ok() {
+                ops.push(Op::RmDir { path });
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn flatten(dir: &Path, remove_empty: bool) -> std::io::Result<Op> {
+    let mut ops = vec![];
+    if let Err(e) = flatten_into(dir, remove_empty, &mut ops) {
+        let _ = undo(&Op::Batch { ops });
+        return Err(e);
+    }
+    Ok(Op::Batch { ops })
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct TrashSummary {
+    pub items: usize,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+fn tally(p: &Path, abort: &AtomicBool, summary: &mut TrashSummary) {
+    if abort.load(Ordering::Relaxed) {
+        return;
+    }
+    let Ok(meta) = fs::symlink_metadata(p) else {
+        return;
+    };
+    if meta.is_dir() {
+        for e in fs::read_dir(p).into_iter().flatten().flatten() {
+            tally(&e.path(), abort, summary);
+        }
+    } else {
+        summary.files += 1;
+        summary.bytes += meta.len();
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct DirSize {
+    pub bytes: u64,
+    // Set when some entries couldn't be read, so the total is a lower bound.
+    pub partial: bool,
+}
+
+fn sum_dir(p: &Path, abort: &AtomicBool, size: &mut DirSize) {
+    if abort.load(Ordering::Relaxed) {
+        return;
+    }
+    let Ok(meta) = fs::symlink_metadata(p) else {
+        size.partial = true;
+        return;
+    };
+    if !meta.is_dir() {
+        size.bytes += meta.len();
+        return;
+    }
+    let Ok(entries) = fs::read_dir(p) else {
+        size.partial = true;
+        return;
+    };
+    for e in entries {
+        match e {
+            Ok(e) => sum_dir(&e.path(), abort, size),
+            Err(_) => size.partial = true,
+        }
+    }
+}
+
+pub fn dir_size(dir: &Path, abort: &AtomicBool) -> Option<DirSize> {
+    let mut size = DirSize::default();
+    sum_dir(dir, abort, &mut size);
+    (!abort.load(Ordering::Relaxed)).then_some(size)
+}
+
+pub fn trash_items() -> Vec<PathBuf> {
+    fs::read_dir(config::trash_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .collect()
+}
+
+/// Counts the files under `items`. Returns `None` if aborted.
+pub fn summarize(items: &[PathBuf], abort: &AtomicBool) -> Option<TrashSummary> {
+    let mut summary = TrashSummary::default();
+    for item in items {
+        summary.items += 1;
+        tally(item, abort, &mut summary);
+    }
+    (!abort.load(Ordering::Relaxed)).then_some(summary)
+}
+
+pub fn trash_summary() -> TrashSummary {
+    summarize(&trash_items(), &AtomicBool::new(false)).unwrap_or_default()
+}
+
+// Moving into the trash keeps the mtime, so on Unix the change time is a closer
+// match for when an item was trashed.
+#[cfg(unix)]
+fn trashed_at(meta: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    let secs = u64::try_from(meta.ctime()).ok()?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+}
+
+#[cfg(not(unix))]
+fn trashed_at(meta: &fs::Metadata) -> Option<SystemTime> {
+    meta.modified().ok()
+}
+
+/// Removes entries of REX's own trash that were put there more than
+/// `retention_days` ago and returns how many went. A retention of 0 disables
+/// purging. The system trash is never touched.
+pub fn purge_trash(retention_days: u64) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+    let Some(cutoff) =
+        SystemTime::now().checked_sub(Duration::from_secs(retention_days.saturating_mul(86_400)))
+    else {
+        return 0;
+    };
+    trash_items()
+        .iter()
+        .filter(|item| {
+            fs::symlink_metadata(item)
+                .ok()
+                .and_then(|m| trashed_at(&m))
+                .is_some_and(|t| t < cutoff)
+        })
+        .filter(|item| remove_rec(item).is_ok())
+        .count()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HashKind {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashKind {
+    pub const ALL: [HashKind; 3] = [HashKind::Md5, HashKind::Sha1, HashKind::Sha256];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HashKind::Md5 => "MD5",
+            HashKind::Sha1 => "SHA-1",
+            HashKind::Sha256 => "SHA-256",
+        }
+    }
+}
+
+fn digest_file<D: sha2::Digest>(path: &Path, abort: &AtomicBool) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = D::new();
+    let mut buf = vec![0; COPY_CHUNK];
+    loop {
+        if abort.load(Ordering::Relaxed) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Hex digest of a file's contents, read in chunks so `abort` is honoured on
+/// large files.
+pub fn hash_file(path: &Path, kind: HashKind, abort: &AtomicBool) -> std::io::Result<String> {
+    match kind {
+        HashKind::Md5 => digest_file::<md5::Md5>(path, abort),
+        HashKind::Sha1 => digest_file::<sha1::Sha1>(path, abort),
+        HashKind::Sha256 => digest_file::<sha2::Sha256>(path, abort),
+    }
+}
+
+pub fn delete_permanent(p: &Path) -> std::io::Result<()> {
+    remove_rec(p)
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+pub fn parse_size(text: &str) -> Option<u64> {
+    let text = text.trim().to_uppercase();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let power = match suffix.trim().strip_suffix('B').unwrap_or(suffix.trim()) {
+        "" => 0,
+        "K" => 1,
+        "M" => 2,
+        "G" => 3,
+        "T" => 4,
+        _ => return None,
+    };
+    let bytes = number * 1024f64.powi(power);
+    (bytes <= u64::MAX as f64).then_some(bytes.round() as u64)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    pub fn from_system_time(t: SystemTime) -> Self {
+        let secs = t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+        let (days, rem) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u32,
+            minute: (rem % 3600 / 60) as u32,
+            second: (rem % 60) as u32,
+        }
+    }
+
+    pub fn days_in_month(year: i64, month: u32) -> u32 {
+        match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    pub fn to_system_time(self) -> Result<SystemTime, String> {
+        if self.year < 1970 {
+            return Err("Dates before 1970 are not supported".into());
+        }
+        if !(1..=12).contains(&self.month) {
+            return Err("Month must be between 1 and 12".into());
+        }
+        let max_day = Self::days_in_month(self.year, self.month);
+        if self.day < 1 || self.day > max_day {
+            return Err(format!("Day must be between 1 and {max_day}"));
+        }
+        if self.hour > 23 || self.minute > 59 || self.second > 59 {
+            return Err("Invalid time of day".into());
+        }
+        let y = if self.month <= 2 {
+            self.year - 1
+        } else {
+            self.year
+        };
+        let m = i64::from(self.month);
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+        let secs = days * 86400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second);
+        Ok(UNIX_EPOCH + Duration::from_secs(secs as u64))
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(unix)]
+pub fn mode_of(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn mode_of(_meta: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "permission bits are only available on Unix",
+    ))
+}
+
+/// `rwxr-xr-x` style rendering of the owner/group/other bits.
+pub fn format_mode(mode: u32) -> String {
+    (0..9)
+        .map(|i| {
+            if mode & (0o400 >> i) == 0 {
+                '-'
+            } else {
+                ['r', 'w', 'x'][i % 3]
+            }
+        })
+        .collect()
+}
+
+pub fn chmod(path: &Path, mode: u32) -> io::Result<Op> {
+    let old_mode = mode_of(&fs::metadata(path)?).unwrap_or(0);
+    set_mode(path, mode)?;
+    Ok(Op::Chmod {
+        path: path.to_path_buf(),
+        old_mode,
+        new_mode: mode,
+    })
+}
+
+pub fn modified(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path)?.modified()
+}
+
+pub fn set_mtime(path: &Path, time: SystemTime) -> io::Result<Op> {
+    let old = modified(path)?;
+    filetime::set_file_mtime(path, FileTime::from_system_time(time))?;
+    Ok(Op::SetMtime {
+        path: path.to_path_buf(),
+        old,
+        new: time,
+    })
+}
+
+/// Takes away an item an operation created. It goes to the trash, and only if
+/// nothing has changed since, so later work in it is never lost.
+fn take_back(path: &Path, stamp: &Option<Stamp>) -> std::io::Result<()> {
+    if !unchanged(path, stamp) {
+        return Err(io::Error::other(format!(
+            "{} has changed since, so it was left alone",
+            path.display()
+        )));
+    }
+    delete_to_trash(path).map(drop)
+}
+
 pub fn undo(op: &Op) -> std::io::Result<()> {
     match op {
-        Op::Copy { to, .. } => super::fs_ops::remove_rec(to),
-        Op::Move { from, to } | Op::Rename { from, to } => super::fs_ops::move_rec(to, from),
-        Op::Delete { trashed, original } => super::fs_ops::move_rec(trashed, original),
-        Op::MkDir { path } => super::fs_ops::remove_rec(path),
-        Op::Touch { path } => super::fs_ops::remove_rec(path),
+        Op::Copy { to, stamp, .. }
+        | Op::CopyStructure { to, stamp, .. }
+        | Op::Extract { to, stamp, .. }
+        | Op::Compress { to, stamp, .. } => take_back(to, stamp),
+        Op::Move { from, to } | Op::Rename { from, to } => {
+            if from.exists() && !same_file(from, to) {
+                return Err(in_the_way(from));
+            }
+            super::fs_ops::move_rec(to, from)
+        }
+        Op::Delete { trashed, original } => {
+            if original.exists() {
+                return Err(in_the_way(original));
+            }
+            super::fs_ops::move_rec(trashed, original)
+        }
+        Op::Trash { original } => restore_from_os_trash(original),
+        Op::MkDir { path, stamp } | Op::Touch { path, stamp } => take_back(path, stamp),
+        Op::Symlink { link, .. } => remove_link(link),
+        Op::RmDir { path } => fs::create_dir_all(path),
+        Op::SetMtime { path, old, .. } => {
+            filetime::set_file_mtime(path, FileTime::from_system_time(*old))
+        }
+        Op::Chmod { path, old_mode, .. } => set_mode(path, *old_mode),
+        Op::Batch { ops } => ops.iter().rev().try_for_each(undo),
+    }
+}
+
+/// Redoes `op` and returns it with fresh stamps for whatever it recreated.
+pub fn redo(op: &Op, preserve_times: bool) -> std::io::Result<Op> {
+    redo_in_place(op, preserve_times)?;
+    Ok(op.restamped())
+}
+
+fn redo_in_place(op: &Op, preserve_times: bool) -> std::io::Result<()> {
+    // Whatever took the destination's place since the undo is never replaced.
+    match op {
+        Op::Copy { to, .. } | Op::Compress { to, .. } | Op::Extract { to, .. } if to.exists() => {
+            return Err(in_the_way(to));
+        }
+        Op::Move { from, to } | Op::Rename { from, to } if to.exists() && !same_file(from, to) => {
+            return Err(in_the_way(to));
+        }
+        _ => {}
+    }
+    match op {
+        Op::Copy { from, to, .. } => copy_rec(from, to, preserve_times, &mut no_progress),
+        Op::CopyStructure { from, to, .. } => mkdir_tree(from, to, preserve_times),
+        Op::Move { from, to } | Op::Rename { from, to } => move_rec(from, to),
+        Op::Delete { trashed, original } => {
+            fs::create_dir_all(config::trash_dir())?;
+            move_rec(original, trashed)
+        }
+        Op::Trash { original } => trash::delete(original).map_err(trash_error),
+        Op::MkDir { path, .. } => fs::create_dir_all(path),
+        Op::Symlink { target, link } => make_link(target, link),
+        Op::Compress { sources, to, .. } => zip_to(sources, to, &mut |_, _, _| true).map(drop),
+        Op::Extract { archive, to, .. } => {
+            fs::create_dir_all(to)?;
+            unzip_into(archive, to, &mut |_, _, _| true).inspect_err(|_| {
+                let _ = remove_rec(to);
+            })
+        }
+        Op::Touch { path, .. } => fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map(drop),
+        Op::RmDir { path } => fs::remove_dir(path),
+        Op::SetMtime { path, new, .. } => {
+            filetime::set_file_mtime(path, FileTime::from_system_time(*new))
+        }
+        Op::Chmod { path, new_mode, .. } => set_mode(path, *new_mode),
+        Op::Batch { ops } => ops
+            .iter()
+            .try_for_each(|op| redo_in_place(op, preserve_times)),
+    }
+}
+
+/// An empty folder under the system temp dir for a test to work in.
+#[cfg(test)]
+pub fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rex-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paste(from: &Path, to_dir: &Path) -> io::Result<Op> {
+        copy(from, to_dir, Conflict::KeepBoth, true, &mut no_progress)
+    }
+
+    #[test]
+    fn parse_size_reads_units() {
+        assert_eq!(parse_size("2.5GB"), Some(2_684_354_560));
+        assert_eq!(parse_size("1 TB"), Some(1 << 40));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("512B"), Some(512));
+        assert_eq!(parse_size(" 7 kb "), Some(7 * 1024));
+        assert_eq!(parse_size("3m"), Some(3 * 1024 * 1024));
+        assert_eq!(parse_size("1.5k"), Some(1536));
+    }
+
+    #[test]
+    fn parse_size_rejects_garbage() {
+        for text in ["", "abc", "GB", "12XB", "-5", "1.2.3", "5 KBB", "1e3"] {
+            assert_eq!(parse_size(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn paste_into_self_is_refused() {
+        let root = scratch("into-self");
+        let dir = root.join("a");
+        fs::create_dir(&dir).unwrap();
+        assert!(is_inside(&dir, &dir));
+        let err = paste(&dir, &dir).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = mv(&dir, &dir, Conflict::KeepBoth, &mut no_progress).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn paste_into_child_is_refused() {
+        let root = scratch("into-child");
+        let dir = root.join("a");
+        let child = dir.join("b").join("c");
+        fs::create_dir_all(&child).unwrap();
+        assert!(is_inside(&dir, &child));
+        let err = paste(&dir, &child).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = mv(&dir, &child, Conflict::KeepBoth, &mut no_progress).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = copy_tree_structure(&dir, &child, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_dir(&child).unwrap().count(), 0);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn paste_into_sibling_with_same_prefix_is_allowed() {
+        let root = scratch("into-sibling");
+        let (a, ab) = (root.join("a"), root.join("ab"));
+        fs::create_dir(&a).unwrap();
+        fs::create_dir(&ab).unwrap();
+        assert!(!is_inside(&a, &ab));
+        paste(&a, &ab).unwrap();
+        assert!(ab.join("a").is_dir());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_to_ancestor_is_copied_as_a_link() {
+        let root = scratch("link-to-ancestor");
+        let child = root.join("a").join("b");
+        fs::create_dir_all(&child).unwrap();
+        let link = root.join("up");
+        std::os::unix::fs::symlink(&root, &link).unwrap();
+        assert!(!is_inside(&link, &child));
+        paste(&link, &child).unwrap();
+        assert_eq!(fs::read_link(child.join("up")).unwrap(), root);
+        fs::remove_dir_all(root).unwrap();
+    }
+    #[test]
+    fn overwrite_rename_can_be_undone_after_a_restart() {
+        let root = scratch("overwrite-rename");
+        let (from, to) = (root.join("new.txt"), root.join("old.txt"));
+        fs::write(&from, "new").unwrap();
+        fs::write(&to, "old").unwrap();
+        let op = rename(&from, "old.txt", true).unwrap();
+        assert!(matches!(&op, Op::Batch { ops } if ops.len() == 2));
+        let saved = serde_json::to_string(&vec![op]).unwrap();
+        let reloaded: Vec<Op> = serde_json::from_str(&saved).unwrap();
+        assert!(reloaded[0].can_undo());
+        undo(&reloaded[0]).unwrap();
+        assert_eq!(fs::read_to_string(&from).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&to).unwrap(), "old");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn redo_leaves_a_new_item_in_the_way_alone() {
+        let root = scratch("redo-in-the-way");
+        let (from, to) = (root.join("a.txt"), root.join("b.txt"));
+        fs::write(&from, "copied").unwrap();
+        fs::write(&to, "mine").unwrap();
+        let copy = Op::Copy {
+            from: from.clone(),
+            to: to.clone(),
+            stamp: None,
+        };
+        let err = redo(&copy, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let rename = Op::Rename {
+            from: from.clone(),
+            to: to.clone(),
+        };
+        let err = redo(&rename, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&from).unwrap(), "copied");
+        assert_eq!(fs::read_to_string(&to).unwrap(), "mine");
+        fs::remove_dir_all(root).unwrap();
     }
 }
@@ -1,5 +1,21 @@
-use super::fs_ops::Op;
-use std::{collections::VecDeque, path::PathBuf};
+use super::{config, fs_ops::Op};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+};
+
+pub fn remap(p: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    p.strip_prefix(from).ok().map(|rest| {
+        if rest.as_os_str().is_empty() {
+            to.to_path_buf()
+        } else {
+            to.join(rest)
+        }
+    })
+}
+
+// How many entries of each stack survive a restart.
+const NAV_SAVED: usize = 50;
 
 #[derive(Default)]
 pub struct NavHistory {
@@ -8,6 +24,14 @@ pub struct NavHistory {
 }
 
 impl NavHistory {
+    pub fn load() -> Self {
+        let (back, forward) = config::load_nav_history();
+        Self { back, forward }
+    }
+    pub fn save(&self) {
+        let tail = |v: &[PathBuf]| v[v.len().saturating_sub(NAV_SAVED)..].to_vec();
+        config::save_nav_history(&tail(&self.back), &tail(&self.forward));
+    }
     pub fn push(&mut self, cur: PathBuf) {
         self.back.push(cur);
         self.forward.clear();
@@ -34,11 +58,22 @@ impl NavHistory {
     pub fn can_forward(&self) -> bool {
         !self.forward.is_empty()
     }
+    pub fn recent(&self) -> impl Iterator<Item = &PathBuf> {
+        self.back.iter().rev()
+    }
+    pub fn remap(&mut self, from: &Path, to: &Path) {
+        for p in self.back.iter_mut().chain(self.forward.iter_mut()) {
+            if let Some(moved) = remap(p, from, to) {
+                *p = moved;
+            }
+        }
+    }
 }
 
 #[derive(Default)]
 pub struct OpsHistory {
     pub undo: VecDeque<Op>,
+    pub redo: VecDeque<Op>,
     pub capacity: usize,
 }
 
@@ -46,16 +81,56 @@ impl OpsHistory {
     pub fn new(cap: usize) -> Self {
         Self {
             undo: VecDeque::new(),
+            redo: VecDeque::new(),
             capacity: cap,
         }
     }
-    pub fn push(&mut self, op: Op) {
+    /// Restores the undo stack saved by `save`, keeping only operations that
+    /// can still be undone.
+    pub fn load(&mut self) {
+        let saved: Vec<Op> = std::fs::read_to_string(config::undo_history_path())
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        self.undo = saved.into_iter().filter(Op::can_undo).collect();
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+    pub fn save(&self) {
+        let path = config::undo_history_path();
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&self.undo) {
+            let _ = std::fs::write(path, json);
+        }
+    }
+    fn record(&mut self, op: Op) {
         if self.undo.len() == self.capacity {
             self.undo.pop_front();
         }
         self.undo.push_back(op);
     }
+    pub fn push(&mut self, op: Op) {
+        self.record(op);
+        self.redo.clear();
+    }
+    pub fn next_undo(&self) -> Option<&Op> {
+        self.undo.back()
+    }
+    pub fn next_redo(&self) -> Option<&Op> {
+        self.redo.back()
+    }
     pub fn pop_undo(&mut self) -> Option<Op> {
-        self.undo.pop_back()
+        let op = self.undo.pop_back()?;
+        self.redo.push_back(op.clone());
+        Some(op)
+    }
+    pub fn pop_redo(&mut self) -> Option<Op> {
+        let op = self.redo.pop_back()?;
+        self.record(op.clone());
+        Some(op)
     }
 }
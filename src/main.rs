@@ -1,27 +1,40 @@
 #![allow(clippy::needless_return)]
 
+use config::ResultClick;
 use eframe::{
     Frame, egui,
-    egui::{Button, Context, Key, ProgressBar, TopBottomPanel},
+    egui::{Button, Context, Key, TopBottomPanel},
 };
 use egui::{RichText, TextEdit};
+use queue::JobOutcome;
+use regex::RegexBuilder;
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
     path::{Path, PathBuf},
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
         mpsc::{self, Receiver},
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 mod browser;
+mod cli;
 mod clipboard;
 mod config;
+mod dupes;
 mod fs_ops;
 mod history;
 mod platform;
+mod preview;
+mod queue;
 mod searcher;
+mod thumbnails;
+
+const HISTORY_SHOWN: usize = 20;
+const RECENT_DIRS: usize = 25;
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
 
 #[derive(Clone)]
 struct Toast {
@@ -46,6 +59,18 @@ impl Toaster {
         });
     }
 
+    fn warn(&mut self, text: impl Into<String>) {
+        let text = format!("⚠ {}", text.into());
+        if self.items.iter().any(|t| t.text == text) {
+            return;
+        }
+        self.items.push(Toast {
+            text,
+            created: Instant::now(),
+            ttl: Duration::from_secs(6),
+        });
+    }
+
     fn error(&mut self, text: impl Into<String>) {
         self.items.push(Toast {
             text: format!("❗ {}", text.into()),
@@ -68,100 +93,608 @@ enum CreateKind {
     File,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ResultSort {
+    Found,
+    Name,
+    Path,
+    Depth,
+    Size,
+    Modified,
+}
+
+impl ResultSort {
+    const ALL: [ResultSort; 6] = [
+        ResultSort::Found,
+        ResultSort::Name,
+        ResultSort::Path,
+        ResultSort::Depth,
+        ResultSort::Size,
+        ResultSort::Modified,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ResultSort::Found => "Order found",
+            ResultSort::Name => "Name",
+            ResultSort::Path => "Full path",
+            ResultSort::Depth => "Path depth",
+            ResultSort::Size => "Largest first",
+            ResultSort::Modified => "Newest first",
+        }
+    }
+}
+
 enum ViewMode {
     Browsing,
+    Drives {
+        drives: Vec<PathBuf>,
+    },
     Searching {
-        results: Vec<PathBuf>,
+        results: Vec<searcher::SearchMsg>,
         rx_results: Receiver<searcher::SearchMsg>,
         rx_prog: Receiver<searcher::ProgressMsg>,
         abort: Arc<AtomicBool>,
         scanned_files: u64,
         scanned_dirs: u64,
+        truncated: bool,
         done: bool,
         started_at: Instant,
+        elapsed: Duration,
     },
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsAction {
+    Export,
+    Import,
+}
+
+struct BatchReport {
+    label: String,
+    cancelled: bool,
+    succeeded: Vec<PathBuf>,
+    failed: Vec<(PathBuf, String)>,
+}
+
+impl BatchReport {
+    fn text(&self) -> String {
+        let mut out = format!(
+            "{}\nSucceeded: {}\nFailed: {}\n",
+            self.label,
+            self.succeeded.len(),
+            self.failed.len()
+        );
+        for (p, reason) in &self.failed {
+            out.push_str(&format!("FAILED {}: {reason}\n", p.display()));
+        }
+        for p in &self.succeeded {
+            out.push_str(&format!("OK {}\n", p.display()));
+        }
+        out
+    }
+}
+
+#[derive(Default)]
+struct Palette {
+    query: String,
+    selected: usize,
+}
+
+// A group's file size, and each path paired with whether it is marked for
+// the trash.
+type MarkedGroup = (u64, Vec<(PathBuf, bool)>);
+
+struct DupeFinder {
+    root: PathBuf,
+    rx: Receiver<dupes::DupeMsg>,
+    abort: Arc<AtomicBool>,
+    scanned: Option<u64>,
+    hashed: (u64, u64),
+    groups: Option<Vec<MarkedGroup>>,
+}
+
+struct TrashConfirm {
+    paths: Vec<PathBuf>,
+    summary: Option<fs_ops::TrashSummary>,
+    rx: Receiver<fs_ops::TrashSummary>,
+    abort: Arc<AtomicBool>,
+    dont_ask: bool,
+}
+
+struct HashDialog {
+    kind: fs_ops::HashKind,
+    results: Vec<(PathBuf, Option<Result<String, String>>)>,
+    rx: Receiver<(usize, Result<String, String>)>,
+    abort: Arc<AtomicBool>,
+}
+
+impl HashDialog {
+    // Same layout as `sha256sum` and friends, so the output can be checked with them.
+    fn text(&self) -> String {
+        self.results
+            .iter()
+            .filter_map(|(p, r)| match r {
+                Some(Ok(digest)) => Some(format!("{digest}  {}\n", p.display())),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+struct PendingTransfer {
+    mode: clipboard::Mode,
+    target_dir: PathBuf,
+    resolved: Vec<(PathBuf, fs_ops::Conflict)>,
+    conflicts: VecDeque<PathBuf>,
+    apply_to_all: bool,
+}
+
 struct AppData {
     current_path: PathBuf,
     path_edit: String,
+    editing_path: bool,
+    focus_path_edit: bool,
 
     pinned: Vec<PathBuf>,
 
     search_query: String,
+    search_edited_at: Option<Instant>,
+    search_options: searcher::SearchOptions,
+    search_extensions: String,
+    search_min_size: String,
+    search_max_size: String,
+    search_modified_days: Option<u32>,
+    search_modified_custom: bool,
+    show_search_filters: bool,
+    show_preview: bool,
+    preview: preview::Preview,
     mode: ViewMode,
+    result_click: ResultClick,
+    quiet_auto_rename: bool,
+    warn_extension_change: bool,
+    delete_permanently: bool,
+    confirm_permanent_delete: Option<Vec<PathBuf>>,
+    confirm_trash: Option<TrashConfirm>,
+    confirm_deletes: bool,
+    skip_delete_confirm: bool,
+    pending_rename: Option<(PathBuf, String)>,
+    confirm_rename_overwrite: Option<(PathBuf, String)>,
+    results_sort: ResultSort,
+    results_filter: String,
+    results_filter_regex: bool,
+    // The compiled refine regex and the text it was built from.
+    results_filter_re: Option<(String, Option<regex::Regex>)>,
+    auto_refresh_secs: u64,
+    trash_retention_days: u64,
+    preserve_times: bool,
+    last_auto_refresh: Instant,
 
     nav_hist: history::NavHistory,
+    // Distinct folders, most recent first.
+    recent_dirs: Vec<PathBuf>,
 
     ops_hist: history::OpsHistory,
 
     autocomplete: Vec<String>,
     scale_factor: f32,
+    scale_bounds: config::ScaleBounds,
+    theme: config::Theme,
+    low_space: config::LowSpace,
+    max_io_concurrency: usize,
     browser: browser::FileBrowser,
 
     clipboard: clipboard::Clipboard,
 
     open_with_buffer: String,
     open_with_target: Option<PathBuf>,
+    open_with_last: HashMap<String, String>,
+    network_shares: Vec<PathBuf>,
+    search_history: Vec<String>,
+    /// The newest history entry came from a live (debounced) search.
+    search_history_live: bool,
+    tags: HashMap<PathBuf, String>,
+    tag_dialog: Option<(PathBuf, String)>,
+    tag_filter: Option<String>,
+    palette: Option<Palette>,
+    batch_report: Option<BatchReport>,
+    connect_dialog: Option<String>,
 
     toasts: Toaster,
+    queue: queue::OpQueue,
+    item_errors: Vec<(PathBuf, String, Instant)>,
 
     create_dialog: Option<(CreateKind, PathBuf)>,
     create_name_buffer: String,
+
+    flatten_target: Option<PathBuf>,
+    flatten_remove_empty: bool,
+    mtime_dialog: Option<(PathBuf, fs_ops::DateTime)>,
+    // Path, the mode being edited, and its octal text.
+    chmod_dialog: Option<(PathBuf, u32, String)>,
+
+    pending_transfer: Option<PendingTransfer>,
+
+    empty_trash_dialog: Option<Receiver<fs_ops::TrashSummary>>,
+    hash_dialog: Option<HashDialog>,
+    dupe_finder: Option<DupeFinder>,
+    trash_summary: Option<fs_ops::TrashSummary>,
+    settings_dialog: Option<SettingsAction>,
+    settings_path_buffer: String,
+    pending_import: Option<config::SettingsBundle>,
 }
 
 impl Default for AppData {
     fn default() -> Self {
         let current_path = std::env::current_dir().unwrap_or_else(|_| config::os_root());
-        Self {
+        let mut app = Self {
             path_edit: current_path.display().to_string(),
+            editing_path: false,
+            focus_path_edit: false,
             current_path,
-            pinned: config::load_pinned(),
+            pinned: vec![],
             search_query: String::new(),
+            search_edited_at: None,
+            search_options: searcher::SearchOptions::default(),
+            search_extensions: String::new(),
+            search_min_size: String::new(),
+            search_max_size: String::new(),
+            search_modified_days: None,
+            search_modified_custom: false,
+            show_search_filters: false,
+            show_preview: false,
+            preview: preview::Preview::new(),
             mode: ViewMode::Browsing,
+            result_click: ResultClick::Navigate,
+            auto_refresh_secs: 0,
+            trash_retention_days: 0,
+            preserve_times: true,
+            last_auto_refresh: Instant::now(),
+            results_sort: ResultSort::Found,
+            results_filter: String::new(),
+            results_filter_regex: false,
+            results_filter_re: None,
+            quiet_auto_rename: false,
+            warn_extension_change: false,
+            delete_permanently: false,
+            confirm_permanent_delete: None,
+            confirm_trash: None,
+            confirm_deletes: true,
+            skip_delete_confirm: false,
+            pending_rename: None,
+            confirm_rename_overwrite: None,
             nav_hist: history::NavHistory::default(),
+            recent_dirs: vec![],
             ops_hist: history::OpsHistory::new(64),
             autocomplete: vec![],
-            scale_factor: config::load_scale(),
+            scale_factor: 1.0,
+            theme: config::Theme::System,
+            scale_bounds: config::ScaleBounds::default(),
+            low_space: config::LowSpace::default(),
+            max_io_concurrency: 1,
             browser: browser::FileBrowser::new(),
             clipboard: clipboard::Clipboard::default(),
             open_with_buffer: String::new(),
             open_with_target: None,
+            open_with_last: HashMap::new(),
+            network_shares: vec![],
+            search_history: vec![],
+            search_history_live: false,
+            tags: HashMap::new(),
+            tag_dialog: None,
+            tag_filter: None,
+            palette: None,
+            batch_report: None,
+            connect_dialog: None,
             toasts: Toaster::new(),
+            queue: queue::OpQueue::new(),
+            item_errors: vec![],
             create_dialog: None,
             create_name_buffer: String::new(),
-        }
+            flatten_target: None,
+            flatten_remove_empty: true,
+            mtime_dialog: None,
+            chmod_dialog: None,
+            pending_transfer: None,
+            empty_trash_dialog: None,
+            hash_dialog: None,
+            dupe_finder: None,
+            trash_summary: None,
+            settings_dialog: None,
+            settings_path_buffer: String::new(),
+            pending_import: None,
+        };
+        app.load_settings();
+        app
     }
 }
 
 impl Drop for AppData {
     fn drop(&mut self) {
-        config::save_pinned(&self.pinned);
-        config::save_scale(self.scale_factor);
+        self.save_settings();
     }
 }
 
 impl AppData {
+    fn load_settings(&mut self) {
+        self.pinned = config::load_pinned();
+        self.nav_hist = history::NavHistory::load();
+        self.recent_dirs = config::load_recent_dirs();
+        self.ops_hist.load();
+        self.network_shares = config::load_network_shares();
+        self.search_history = config::load_search_history();
+        self.tags = config::load_tags();
+        let prefs = config::Prefs::load();
+        self.scale_bounds = prefs.scale_bounds;
+        self.scale_factor = self.scale_bounds.clamp(prefs.scale);
+        self.theme = prefs.theme;
+        self.browser.show_hidden = prefs.show_hidden;
+        self.browser.sort_key = prefs.sort_key;
+        self.browser.sort_desc = prefs.sort_desc;
+        self.browser.smart_sort = prefs.smart_sort;
+        self.browser.icon_set = prefs.icon_set;
+        self.browser.view_kind = prefs.view_kind;
+        self.show_preview = prefs.show_preview;
+        self.confirm_deletes = prefs.confirm_deletes;
+        self.delete_permanently = prefs.delete_permanently;
+        self.trash_retention_days = prefs.trash_retention_days;
+        self.low_space = prefs.low_space;
+        self.max_io_concurrency = prefs.max_io_concurrency;
+        self.browser.set_io_concurrency(self.max_io_concurrency);
+        self.search_options.mode = prefs.search_mode;
+        self.search_options.max_content_bytes = prefs.max_content_bytes;
+        self.search_options.max_results = prefs.search_max_results;
+        self.result_click = prefs.result_click;
+        self.auto_refresh_secs = prefs.auto_refresh_secs;
+        self.preserve_times = prefs.preserve_times;
+        self.quiet_auto_rename = prefs.quiet_auto_rename;
+        self.warn_extension_change = prefs.warn_extension_change;
+    }
+
+    fn save_settings(&self) {
+        config::save_pinned(&self.pinned);
+        self.nav_hist.save();
+        config::save_recent_dirs(&self.recent_dirs);
+        self.ops_hist.save();
+        config::save_network_shares(&self.network_shares);
+        config::save_search_history(&self.search_history);
+        config::save_tags(&self.tags);
+        config::Prefs {
+            scale: self.scale_bounds.clamp(self.scale_factor),
+            scale_bounds: self.scale_bounds,
+            theme: self.theme,
+            show_hidden: self.browser.show_hidden,
+            sort_key: self.browser.sort_key,
+            sort_desc: self.browser.sort_desc,
+            smart_sort: self.browser.smart_sort,
+            icon_set: self.browser.icon_set,
+            view_kind: self.browser.view_kind,
+            show_preview: self.show_preview,
+            confirm_deletes: self.confirm_deletes,
+            delete_permanently: self.delete_permanently,
+            trash_retention_days: self.trash_retention_days,
+            low_space: self.low_space,
+            max_io_concurrency: self.max_io_concurrency,
+            search_mode: self.search_options.mode,
+            max_content_bytes: self.search_options.max_content_bytes,
+            search_max_results: self.search_options.max_results,
+            result_click: self.result_click,
+            auto_refresh_secs: self.auto_refresh_secs,
+            preserve_times: self.preserve_times,
+            quiet_auto_rename: self.quiet_auto_rename,
+            warn_extension_change: self.warn_extension_change,
+        }
+        .save();
+    }
+
+    fn import_settings(&mut self, replace: bool) {
+        let Some(bundle) = self.pending_import.take() else {
+            return;
+        };
+        self.save_settings();
+        config::apply_bundle(&bundle, replace);
+        self.load_settings();
+        self.browser.invalidate();
+        self.toasts.info(format!(
+            "Imported {} setting(s) and {} pin(s).",
+            bundle.prefs.len(),
+            bundle.pinned.len()
+        ));
+    }
+
     fn navigate_to(&mut self, new_path: PathBuf) {
+        let unc = if cfg!(target_os = "windows") {
+            platform::parse_unc(&new_path.to_string_lossy())
+        } else {
+            None
+        };
+        let new_path = unc.as_ref().map_or(new_path, |(_, full)| full.clone());
         if new_path.exists() && new_path.is_dir() {
+            if let Some((share, _)) = unc {
+                self.network_shares.retain(|s| s != &share);
+                self.network_shares.insert(0, share);
+                self.network_shares.truncate(10);
+            }
+            self.leave_drives();
+            self.browser.cancel_interaction();
             if new_path != self.current_path {
                 self.nav_hist.push(self.current_path.clone());
             }
+            self.recent_dirs.retain(|p| p != &new_path);
+            self.recent_dirs.insert(0, new_path.clone());
+            self.recent_dirs.truncate(RECENT_DIRS);
             self.current_path = new_path.clone();
             self.path_edit = new_path.display().to_string();
             self.browser.invalidate();
+            self.check_free_space(&new_path);
         } else {
-            self.toasts
-                .error("Path does not exist or is not a directory.");
+            match (&unc, std::fs::read_dir(&new_path)) {
+                (Some(_), Err(e)) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                    self.toasts.error(format!(
+                        "Access to {} was denied. Check your network credentials.",
+                        new_path.display()
+                    ))
+                }
+                (Some(_), Err(e)) => self
+                    .toasts
+                    .error(format!("Cannot reach {}: {e}", new_path.display())),
+                _ => self
+                    .toasts
+                    .error("Path does not exist or is not a directory."),
+            }
             self.path_edit = self.current_path.display().to_string();
         }
     }
+    fn check_free_space(&mut self, dir: &Path) {
+        if let Some((available, total)) = platform::free_space(dir)
+            && self.low_space.is_low(available, total)
+        {
+            self.toasts.warn(format!(
+                "Low disk space: {} free of {}",
+                fs_ops::format_size(available),
+                fs_ops::format_size(total)
+            ));
+        }
+    }
+
+    fn palette_entries(&self, query: &str) -> Vec<(String, PathBuf)> {
+        let mut seen = HashSet::new();
+        let pins = self.pinned.iter().map(|p| ("📌", p));
+        let recent = self.nav_hist.recent().map(|p| ("🕘", p));
+        let mut scored: Vec<(i64, String, PathBuf)> = pins
+            .chain(recent)
+            .filter(|(_, p)| seen.insert((*p).clone()))
+            .take(50)
+            .filter_map(|(icon, p)| {
+                let text = p.display().to_string();
+                let score = if query.trim().is_empty() {
+                    0
+                } else {
+                    searcher::fuzzy_score(&text, query)?
+                };
+                Some((score, format!("{icon} {text}"), p.clone()))
+            })
+            .collect();
+        scored.sort_by_key(|e| std::cmp::Reverse(e.0));
+        scored.into_iter().map(|(_, l, p)| (l, p)).collect()
+    }
+
+    fn open_with_key(p: &Path) -> String {
+        p.extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default()
+    }
+
+    fn can_go_up(&self) -> bool {
+        match self.mode {
+            ViewMode::Drives { .. } => false,
+            _ => self.current_path.parent().is_some() || cfg!(target_os = "windows"),
+        }
+    }
+
+    fn go_up(&mut self) {
+        if let Some(parent) = self.current_path.parent() {
+            self.navigate_to(parent.to_path_buf());
+        } else if self.can_go_up() {
+            self.cancel_search();
+            self.mode = ViewMode::Drives {
+                drives: platform::list_drives(),
+            };
+            self.path_edit = "This PC".to_string();
+        }
+    }
+
+    fn breadcrumbs(&mut self, ui: &mut egui::Ui) {
+        if matches!(self.mode, ViewMode::Drives { .. }) {
+            ui.label("This PC");
+            return;
+        }
+        let mut crumbs: Vec<PathBuf> = self
+            .current_path
+            .ancestors()
+            .map(Path::to_path_buf)
+            .collect();
+        crumbs.reverse();
+        let name = |p: &Path| match p.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => p.display().to_string(),
+        };
+
+        // Collapse segments after the root until the rest fits in the space the
+        // text field would take.
+        let font = egui::TextStyle::Button.resolve(ui.style());
+        let padding = ui.spacing().button_padding.x * 2.0 + ui.spacing().item_spacing.x * 3.0;
+        let widths: Vec<f32> = crumbs
+            .iter()
+            .map(|p| {
+                let text =
+                    ui.fonts(|f| f.layout_no_wrap(name(p), font.clone(), egui::Color32::WHITE));
+                text.size().x + padding
+            })
+            .collect();
+        let mut total: f32 = widths.iter().sum();
+        let mut hidden = 0;
+        while total > 400.0 && hidden + 2 < crumbs.len() {
+            total -= widths[1 + hidden];
+            hidden += 1;
+        }
+
+        let mut go = None::<PathBuf>;
+        for (i, p) in crumbs.iter().enumerate() {
+            if i == 1 && hidden > 0 {
+                ui.menu_button("…", |ui| {
+                    for p in &crumbs[1..1 + hidden] {
+                        if ui.button(name(p)).clicked() {
+                            go = Some(p.clone());
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.weak("›");
+            }
+            if (1..1 + hidden).contains(&i) {
+                continue;
+            }
+            if ui.button(name(p)).clicked() {
+                go = Some(p.clone());
+            }
+            if i + 1 < crumbs.len() {
+                ui.weak("›");
+            }
+        }
+        if let Some(p) = go {
+            self.navigate_to(p);
+        }
+    }
+
+    fn go_home(&mut self) {
+        match dirs::home_dir() {
+            Some(home) => self.navigate_to(home),
+            None => self.toasts.error("Could not determine the home directory."),
+        }
+    }
+    fn go_root(&mut self) {
+        self.navigate_to(config::os_root());
+    }
+    fn leave_drives(&mut self) -> bool {
+        if !matches!(self.mode, ViewMode::Drives { .. }) {
+            return false;
+        }
+        self.mode = ViewMode::Browsing;
+        self.path_edit = self.current_path.display().to_string();
+        true
+    }
+
     fn back(&mut self) {
+        if self.leave_drives() {
+            return;
+        }
         let _ = self.nav_hist.back(&mut self.current_path);
         self.path_edit = self.current_path.display().to_string();
         self.browser.invalidate();
     }
     fn forward(&mut self) {
+        self.leave_drives();
         let _ = self.nav_hist.forward(&mut self.current_path);
         self.path_edit = self.current_path.display().to_string();
         self.browser.invalidate();
@@ -202,17 +735,57 @@ impl AppData {
         self.autocomplete = matches;
     }
 
-    fn start_search(&mut self) {
+    fn start_search(&mut self, live: bool) {
+        let mut sizes = [None; 2];
+        for (slot, text) in sizes
+            .iter_mut()
+            .zip([&self.search_min_size, &self.search_max_size])
+        {
+            if text.trim().is_empty() {
+                continue;
+            }
+            match fs_ops::parse_size(text) {
+                Some(bytes) => *slot = Some(bytes),
+                None => {
+                    self.toasts.error(format!("Invalid size '{}'", text.trim()));
+                    return;
+                }
+            }
+        }
+        let [min_size, max_size] = sizes;
         let (tx_res, rx_res) = mpsc::channel::<searcher::SearchMsg>();
         let (tx_prog, rx_prog) = mpsc::channel::<searcher::ProgressMsg>();
         let abort = Arc::new(AtomicBool::new(false));
-        searcher::spawn_search(
+        let options = searcher::SearchOptions {
+            query: self.search_query.clone(),
+            threads: self.max_io_concurrency,
+            extensions: self
+                .search_extensions
+                .split(',')
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            min_size,
+            max_size,
+            modified_within: self
+                .search_modified_days
+                .map(|days| Duration::from_secs(u64::from(days) * 24 * 60 * 60)),
+            ..self.search_options.clone()
+        };
+        if let Err(e) = searcher::spawn_search(
             self.current_path.clone(),
-            self.search_query.clone(),
+            options,
             tx_res,
             tx_prog,
             abort.clone(),
-        );
+        ) {
+            self.toasts.error(format!("Invalid search pattern: {e}"));
+            return;
+        }
+        if let ViewMode::Searching { abort, .. } = &self.mode {
+            abort.store(true, Ordering::Relaxed);
+        }
+        self.remember_search(self.search_query.trim().to_string(), live);
         self.mode = ViewMode::Searching {
             results: vec![],
             rx_results: rx_res,
@@ -220,11 +793,34 @@ impl AppData {
             abort,
             scanned_files: 0,
             scanned_dirs: 0,
+            truncated: false,
             done: false,
             started_at: Instant::now(),
+            elapsed: Duration::ZERO,
         };
     }
 
+    fn remember_search(&mut self, query: String, live: bool) {
+        if query.is_empty() {
+            return;
+        }
+        // Live search fires while typing; fold "fo" into "foo" rather than
+        // keeping every intermediate prefix. Searches the user ran on purpose
+        // are never folded away.
+        if self.search_history_live
+            && self
+                .search_history
+                .first()
+                .is_some_and(|last| query.starts_with(last.as_str()))
+        {
+            self.search_history.remove(0);
+        }
+        self.search_history.retain(|q| q != &query);
+        self.search_history.insert(0, query);
+        self.search_history.truncate(20);
+        self.search_history_live = live;
+    }
+
     fn cancel_search(&mut self) {
         if let ViewMode::Searching { abort, .. } = &self.mode {
             abort.store(true, Ordering::Relaxed);
@@ -237,109 +833,706 @@ impl AppData {
             return;
         }
         let mode = self.clipboard.mode.unwrap();
-        let mut any_ok = false;
-        for item in self.clipboard.items.clone() {
-            let res = match mode {
-                clipboard::Mode::Copy => fs_ops::copy(&item, target_dir),
-                clipboard::Mode::Cut => fs_ops::mv(&item, target_dir),
-            };
+        self.transfer(self.clipboard.items.clone(), mode, target_dir);
+        if mode == clipboard::Mode::Cut {
+            self.clipboard.clear();
+        }
+    }
+
+    fn extract(&mut self, archive: PathBuf) {
+        let label = format!(
+            "Extract {}",
+            archive.file_name().unwrap_or_default().to_string_lossy()
+        );
+        self.queue.enqueue(label, move |job| {
+            let res = fs_ops::extract_zip(&archive, &mut |done, total, current| {
+                job.progress(done, total, Some(current));
+                !job.is_cancelled()
+            });
+            match res {
+                Err(_) if job.is_cancelled() => JobOutcome::default(),
+                res => JobOutcome::single(&archive, res),
+            }
+        });
+    }
+
+    fn request_trash(&mut self, paths: Vec<PathBuf>) {
+        if !self.confirm_deletes || self.skip_delete_confirm {
+            self.trash_paths(paths);
+            return;
+        }
+        if let Some(old) = &self.confirm_trash {
+            old.abort.store(true, Ordering::Relaxed);
+        }
+        let (tx, rx) = mpsc::channel();
+        let abort = Arc::new(AtomicBool::new(false));
+        let counted = paths.clone();
+        let stop = abort.clone();
+        std::thread::spawn(move || {
+            if let Some(summary) = fs_ops::summarize(&counted, &stop) {
+                let _ = tx.send(summary);
+            }
+        });
+        self.confirm_trash = Some(TrashConfirm {
+            paths,
+            summary: None,
+            rx,
+            abort,
+            dont_ask: false,
+        });
+    }
+
+    fn finish_trash_confirm(&mut self, confirmed: bool) {
+        let Some(confirm) = self.confirm_trash.take() else {
+            return;
+        };
+        // The count is only shown in the dialog, so stop walking once it closes.
+        confirm.abort.store(true, Ordering::Relaxed);
+        if confirmed {
+            self.skip_delete_confirm = confirm.dont_ask;
+            self.trash_paths(confirm.paths);
+        }
+    }
+
+    fn trash_paths(&mut self, paths: Vec<PathBuf>) {
+        let label = format!("Move {} to trash", Self::describe_items(&paths));
+        self.queue.enqueue(label, move |job| {
+            job.run_each(&paths, fs_ops::delete_to_trash)
+        });
+    }
+
+    fn find_duplicates(&mut self) {
+        if let Some(old) = &self.dupe_finder {
+            old.abort.store(true, Ordering::Relaxed);
+        }
+        let (tx, rx) = mpsc::channel();
+        let abort = Arc::new(AtomicBool::new(false));
+        dupes::spawn_find(self.current_path.clone(), tx, abort.clone());
+        self.dupe_finder = Some(DupeFinder {
+            root: self.current_path.clone(),
+            rx,
+            abort,
+            scanned: None,
+            hashed: (0, 0),
+            groups: None,
+        });
+    }
+
+    fn request_hashes(&mut self, paths: Vec<PathBuf>, kind: fs_ops::HashKind) {
+        let paths: Vec<PathBuf> = paths.into_iter().filter(|p| p.is_file()).collect();
+        if paths.is_empty() {
+            return;
+        }
+        if let Some(old) = &self.hash_dialog {
+            old.abort.store(true, Ordering::Relaxed);
+        }
+        let (tx, rx) = mpsc::channel();
+        let abort = Arc::new(AtomicBool::new(false));
+        let worker_paths = paths.clone();
+        let worker_abort = abort.clone();
+        std::thread::spawn(move || {
+            for (i, p) in worker_paths.iter().enumerate() {
+                let res = fs_ops::hash_file(p, kind, &worker_abort).map_err(|e| e.to_string());
+                if worker_abort.load(Ordering::Relaxed) || tx.send((i, res)).is_err() {
+                    break;
+                }
+            }
+        });
+        self.hash_dialog = Some(HashDialog {
+            kind,
+            results: paths.into_iter().map(|p| (p, None)).collect(),
+            rx,
+            abort,
+        });
+    }
+
+    fn compress(&mut self, paths: Vec<PathBuf>) {
+        let Some(first) = paths.first() else {
+            return;
+        };
+        let out = fs_ops::default_zip_path(first);
+        let label = format!(
+            "Compress {} into {}",
+            Self::describe_items(&paths),
+            out.file_name().unwrap_or_default().to_string_lossy()
+        );
+        self.queue.enqueue(label, move |job| {
+            let res = fs_ops::zip_to(&paths, &out, &mut |done, total, current| {
+                job.progress(done, total, Some(current));
+                !job.is_cancelled()
+            });
             match res {
+                Err(_) if job.is_cancelled() => JobOutcome::default(),
+                res => JobOutcome::single(&out, res),
+            }
+        });
+    }
+
+    fn link_into(&mut self, target_dir: &Path) {
+        let mut linked = 0;
+        for item in self.clipboard.items.clone() {
+            match fs_ops::symlink(&item, target_dir) {
                 Ok(op) => {
                     self.ops_hist.push(op);
-                    any_ok = true;
+                    linked += 1;
                 }
-                Err(e) => self
-                    .toasts
-                    .error(format!("Paste failed for {}: {e}", item.display())),
+                Err(e) => self.toasts.error(format!(
+                    "Cannot link to {}: {e}",
+                    item.file_name().unwrap_or_default().to_string_lossy()
+                )),
             }
         }
-        if any_ok {
-            if mode == clipboard::Mode::Cut {
-                self.clipboard.clear();
-            }
-            self.toasts.info("Paste complete.");
+        if linked > 0 {
             self.browser.invalidate();
+            self.toasts.info(format!("Created {linked} symlink(s)."));
         }
     }
 
-    fn try_undo(&mut self) {
-        if let Some(op) = self.ops_hist.pop_undo() {
-            match fs_ops::undo(&op) {
-                Ok(()) => {
-                    self.toasts.info("Undid last operation.");
-                    self.browser.invalidate();
-                }
-                Err(e) => {
-                    self.toasts.error(format!("Undo failed: {e}"));
-                }
+    fn transfer(&mut self, mut items: Vec<PathBuf>, mode: clipboard::Mode, target_dir: &Path) {
+        let in_place = |p: &Path| p.parent() == Some(target_dir);
+        let (inside, kept): (Vec<PathBuf>, Vec<PathBuf>) = items
+            .into_iter()
+            .partition(|p| fs_ops::is_inside(p, target_dir));
+        items = kept;
+        if let Some(p) = inside.first() {
+            self.toasts.error(format!(
+                "Cannot paste {} into itself.",
+                p.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        if mode == clipboard::Mode::Cut {
+            let before = items.len();
+            items.retain(|p| !in_place(p));
+            if items.len() < before {
+                self.toasts.info(format!(
+                    "{} item(s) already in {}, not moved.",
+                    before - items.len(),
+                    target_dir.display()
+                ));
+            }
+        }
+        if matches!(mode, clipboard::Mode::Copy | clipboard::Mode::Cut) && !items.is_empty() {
+            self.check_free_space(target_dir);
+        }
+        let mut resolved = vec![];
+        let mut conflicts = VecDeque::new();
+        for item in items {
+            let can_collide = matches!(mode, clipboard::Mode::Copy | clipboard::Mode::Cut);
+            if can_collide && !in_place(&item) && fs_ops::collides(&item, target_dir) {
+                conflicts.push_back(item);
+            } else {
+                resolved.push((item, fs_ops::Conflict::KeepBoth));
             }
         }
+        let pending = PendingTransfer {
+            mode,
+            target_dir: target_dir.to_path_buf(),
+            resolved,
+            conflicts,
+            apply_to_all: false,
+        };
+        if pending.conflicts.is_empty() {
+            self.enqueue_transfer(pending);
+        } else {
+            self.pending_transfer = Some(pending);
+        }
     }
-}
 
-impl eframe::App for AppData {
-    fn update(&mut self, ctx: &Context, _: &mut Frame) {
-        ctx.set_pixels_per_point(self.scale_factor);
-        self.scale_factor = ctx.input(|i| {
-            let mut s = self.scale_factor;
-            if i.modifiers.ctrl {
-                if i.key_pressed(Key::Equals) {
-                    s = (s + 0.1).clamp(0.5, 3.0);
-                }
-                if i.key_pressed(Key::Minus) {
-                    s = (s - 0.1).clamp(0.5, 3.0);
-                }
-                if i.raw_scroll_delta.y.abs() > f32::EPSILON {
-                    s = (s + i.raw_scroll_delta.y * 0.01).clamp(0.5, 3.0);
-                }
-                if i.key_pressed(Key::Num0) {
-                    s = 1.0;
-                }
-                if i.key_pressed(Key::N) {
-                    self.create_dialog = Some((CreateKind::File, self.current_path.clone()));
-                    self.create_name_buffer = "New File.txt".into();
-                }
-                if i.modifiers.shift && i.key_pressed(Key::N) {
-                    self.create_dialog = Some((CreateKind::Folder, self.current_path.clone()));
-                    self.create_name_buffer = "New Folder".into();
-                }
+    fn resolve_conflict(&mut self, choice: Option<fs_ops::Conflict>) {
+        let Some(pending) = &mut self.pending_transfer else {
+            return;
+        };
+        let count = if pending.apply_to_all {
+            pending.conflicts.len()
+        } else {
+            1
+        };
+        for item in pending.conflicts.drain(..count) {
+            if let Some(c) = choice {
+                pending.resolved.push((item, c));
             }
-            s
-        });
+        }
+        if pending.conflicts.is_empty()
+            && let Some(pending) = self.pending_transfer.take()
+        {
+            self.enqueue_transfer(pending);
+        }
+    }
 
-        TopBottomPanel::top("titlebar").show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if ui
-                    .add_enabled(self.nav_hist.can_back(), Button::new("⮌"))
-                    .clicked()
-                {
-                    self.back();
-                }
-                if ui
-                    .add_enabled(self.nav_hist.can_forward(), Button::new("⮎"))
-                    .clicked()
-                {
-                    self.forward();
-                }
+    fn describe_items(paths: &[PathBuf]) -> String {
+        match paths {
+            [p] => p.display().to_string(),
+            _ => format!("{} items", paths.len()),
+        }
+    }
 
-                if ui.button("⬆").clicked() {
-                    if let Some(parent) = self.current_path.parent() {
-                        self.navigate_to(parent.to_path_buf());
+    fn enqueue_transfer(&mut self, pending: PendingTransfer) {
+        let PendingTransfer {
+            mode,
+            target_dir,
+            resolved,
+            ..
+        } = pending;
+        if resolved.is_empty() {
+            return;
+        }
+        let verb = match mode {
+            clipboard::Mode::Copy
+                if resolved
+                    .iter()
+                    .all(|(p, _)| p.parent() == Some(&target_dir)) =>
+            {
+                "Duplicate"
+            }
+            clipboard::Mode::Copy => "Copy",
+            clipboard::Mode::Cut => "Move",
+            clipboard::Mode::CopyStructure => "Copy structure of",
+        };
+        let label = format!(
+            "{verb} {} item(s) into {}",
+            resolved.len(),
+            target_dir.display()
+        );
+        let overwrite: HashSet<PathBuf> = resolved
+            .iter()
+            .filter(|(_, c)| *c == fs_ops::Conflict::Overwrite)
+            .map(|(p, _)| p.clone())
+            .collect();
+        let items: Vec<PathBuf> = resolved.into_iter().map(|(p, _)| p).collect();
+        let preserve_times = self.preserve_times;
+        self.queue.enqueue(label, move |job| {
+            // Moves are usually a rename, so only copies are measured up front.
+            let total = match mode {
+                clipboard::Mode::Copy => job.measure(&items),
+                _ => 0,
+            };
+            let mut copied = 0;
+            let mut progress = |file: &Path, bytes: u64| {
+                copied += bytes;
+                job.bytes(copied, total, file);
+                !job.is_cancelled()
+            };
+            job.run_each(&items, |item| {
+                let conflict = if overwrite.contains(item) {
+                    fs_ops::Conflict::Overwrite
+                } else {
+                    fs_ops::Conflict::KeepBoth
+                };
+                match mode {
+                    clipboard::Mode::Copy => {
+                        fs_ops::copy(item, &target_dir, conflict, preserve_times, &mut progress)
+                    }
+                    clipboard::Mode::Cut => fs_ops::mv(item, &target_dir, conflict, &mut progress),
+                    clipboard::Mode::CopyStructure => {
+                        fs_ops::copy_tree_structure(item, &target_dir, preserve_times)
                     }
                 }
+            })
+        });
+    }
 
-                let resp = ui.add(TextEdit::singleline(&mut self.path_edit).desired_width(400.0));
-                if resp.changed() {
-                    self.update_autocomplete();
-                }
-                let enter = ui.input(|i| i.key_pressed(Key::Enter));
-                if resp.lost_focus() || enter {
-                    self.autocomplete.clear();
+    fn request_empty_trash(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(fs_ops::trash_summary());
+        });
+        self.trash_summary = None;
+        self.empty_trash_dialog = Some(rx);
+    }
+
+    fn empty_trash(&mut self) {
+        let items = fs_ops::trash_items();
+        self.queue.enqueue("Empty REX trash", move |job| {
+            let mut outcome = JobOutcome::default();
+            let total = items.len() as u64;
+            for (i, item) in items.iter().enumerate() {
+                if job.is_cancelled() {
+                    break;
                 }
-                if enter {
-                    self.navigate_to(PathBuf::from(self.path_edit.clone()));
+                job.progress(i as u64, total, Some(item));
+                if let Err(e) = fs_ops::delete_permanent(item) {
+                    outcome.failures.push((item.clone(), e.to_string()));
                 }
-
+            }
+            job.progress(total, total, None);
+            outcome
+        });
+    }
+
+    fn poll_queue(&mut self) {
+        for done in self.queue.poll() {
+            let renamed: Vec<String> = done
+                .outcome
+                .ops
+                .iter()
+                .filter_map(fs_ops::Op::auto_renamed)
+                .map(|(from, to)| format!("{from} → {to}"))
+                .collect();
+            if self.quiet_auto_rename && !renamed.is_empty() {
+                let shown = renamed
+                    .iter()
+                    .take(3)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let more = if renamed.len() > 3 { ", …" } else { "" };
+                self.toasts.info(format!(
+                    "{} item(s) auto-renamed: {shown}{more}",
+                    renamed.len()
+                ));
+            }
+            let first_placed = done.outcome.ops.iter().find_map(|op| match op {
+                fs_ops::Op::Copy { to, .. }
+                | fs_ops::Op::Move { to, .. }
+                | fs_ops::Op::CopyStructure { to, .. }
+                | fs_ops::Op::Extract { to, .. }
+                | fs_ops::Op::Compress { to, .. } => Some(to.clone()),
+                _ => None,
+            });
+            if let Some(to) = first_placed
+                && to.parent() == Some(&self.current_path)
+            {
+                self.browser.select_after_reload(to);
+            }
+            let failed = done.outcome.failures.len();
+            let batch = failed > 0 && done.outcome.ops.len() + failed > 1;
+            if batch {
+                self.batch_report = Some(BatchReport {
+                    label: done.label.clone(),
+                    cancelled: done.cancelled,
+                    succeeded: done
+                        .outcome
+                        .ops
+                        .iter()
+                        .filter_map(|op| op.subject().map(Path::to_path_buf))
+                        .collect(),
+                    failed: done.outcome.failures.clone(),
+                });
+            }
+            for op in done.outcome.ops {
+                self.follow_moves(&op, false);
+                self.ops_hist.push(op);
+            }
+            for (path, reason) in done.outcome.failures {
+                self.item_errors.push((path, reason, Instant::now()));
+            }
+            if done.cancelled {
+                self.toasts.info(format!("Cancelled: {}", done.label));
+            } else if failed > 0 && !batch {
+                self.toasts.error(format!(
+                    "{}: {failed} item(s) failed. Hover the marked entries for details.",
+                    done.label
+                ));
+            } else if failed == 0 {
+                self.toasts.info(format!("Done: {}", done.label));
+            }
+            // Every job touches the filesystem, even ones like permanent deletes
+            // that record no ops, and a cancelled job may have done part of its work.
+            self.browser.invalidate();
+        }
+    }
+
+    fn follow_moves(&mut self, op: &fs_ops::Op, undone: bool) {
+        match op {
+            fs_ops::Op::Move { from, to } | fs_ops::Op::Rename { from, to } => {
+                let (from, to) = if undone { (to, from) } else { (from, to) };
+                for p in &mut self.pinned {
+                    if let Some(moved) = history::remap(p, from, to) {
+                        *p = moved;
+                    }
+                }
+                self.nav_hist.remap(from, to);
+                self.tags = std::mem::take(&mut self.tags)
+                    .into_iter()
+                    .map(|(p, tag)| (history::remap(&p, from, to).unwrap_or(p), tag))
+                    .collect();
+                if let Some(moved) = history::remap(&self.current_path, from, to) {
+                    self.current_path = moved;
+                    self.path_edit = self.current_path.display().to_string();
+                }
+            }
+            fs_ops::Op::Batch { ops } => {
+                for op in ops {
+                    self.follow_moves(op, undone);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn changes_extension(from: &Path, new_name: &str) -> bool {
+        if from.is_dir() {
+            return false;
+        }
+        let Some(old_ext) = from.extension() else {
+            return false;
+        };
+        let new_ext = Path::new(new_name).extension();
+        new_ext.is_none_or(|e| !e.eq_ignore_ascii_case(old_ext))
+    }
+
+    fn apply_rename(&mut self, from: &Path, new_name: &str, overwrite: bool) {
+        match fs_ops::rename(from, new_name, overwrite) {
+            Ok(op) => {
+                self.browser
+                    .select_after_reload(from.with_file_name(new_name));
+                self.follow_moves(&op, false);
+                self.ops_hist.push(op);
+                self.browser.invalidate();
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                self.confirm_rename_overwrite = Some((from.to_path_buf(), new_name.to_string()));
+            }
+            Err(e) => self.toasts.error(format!("Rename failed: {e}")),
+        }
+    }
+
+    // The op only changes stacks once the filesystem call succeeds, so a
+    // failed undo or redo can be retried after clearing what was in the way.
+    fn try_undo(&mut self) {
+        let Some(op) = self.ops_hist.next_undo().cloned() else {
+            return;
+        };
+        match fs_ops::undo(&op) {
+            Ok(()) => {
+                self.ops_hist.pop_undo();
+                self.follow_moves(&op, true);
+                self.toasts.info(format!("Undid: {}", op.describe()));
+                self.browser.invalidate();
+            }
+            Err(e) => self.toasts.error(format!("Undo failed: {e}")),
+        }
+    }
+
+    fn try_redo(&mut self) {
+        let Some(op) = self.ops_hist.next_redo().cloned() else {
+            return;
+        };
+        match fs_ops::redo(&op, self.preserve_times) {
+            Ok(redone) => {
+                self.ops_hist.pop_redo();
+                // Keep what undo checks against in step with the recreated items.
+                if let Some(last) = self.ops_hist.undo.back_mut() {
+                    *last = redone;
+                }
+                self.follow_moves(&op, false);
+                self.toasts.info(format!("Redid: {}", op.describe()));
+                self.browser.invalidate();
+            }
+            Err(e) => self.toasts.error(format!("Redo failed: {e}")),
+        }
+    }
+}
+
+impl eframe::App for AppData {
+    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
+        self.poll_queue();
+        if self.queue.is_busy() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        if self.auto_refresh_secs > 0
+            && matches!(self.mode, ViewMode::Browsing)
+            && !self.browser.is_renaming()
+        {
+            let interval = Duration::from_secs(self.auto_refresh_secs);
+            let elapsed = self.last_auto_refresh.elapsed();
+            if elapsed >= interval {
+                self.browser.invalidate();
+                self.last_auto_refresh = Instant::now();
+                ctx.request_repaint_after(interval);
+            } else {
+                ctx.request_repaint_after(interval - elapsed);
+            }
+        }
+        if let Some(edited) = self.search_edited_at {
+            let elapsed = edited.elapsed();
+            if elapsed >= SEARCH_DEBOUNCE {
+                self.search_edited_at = None;
+                if !self.search_query.trim().is_empty() {
+                    self.start_search(true);
+                }
+            } else {
+                ctx.request_repaint_after(SEARCH_DEBOUNCE - elapsed);
+            }
+        }
+        ctx.set_pixels_per_point(self.scale_factor);
+        ctx.set_theme(match self.theme {
+            config::Theme::System => egui::ThemePreference::System,
+            config::Theme::Dark => egui::ThemePreference::Dark,
+            config::Theme::Light => egui::ThemePreference::Light,
+        });
+        self.scale_factor = ctx.input(|i| {
+            let mut s = self.scale_factor;
+            let bounds = self.scale_bounds;
+            if i.modifiers.ctrl {
+                if i.key_pressed(Key::Equals) {
+                    s = bounds.clamp(s + bounds.step);
+                }
+                if i.key_pressed(Key::Minus) {
+                    s = bounds.clamp(s - bounds.step);
+                }
+                if i.raw_scroll_delta.y.abs() > f32::EPSILON {
+                    s = bounds.clamp(s + i.raw_scroll_delta.y * bounds.step * 0.1);
+                }
+                if i.key_pressed(Key::Num0) {
+                    s = bounds.clamp(1.0);
+                }
+                if i.key_pressed(Key::N) {
+                    self.create_dialog = Some((CreateKind::File, self.current_path.clone()));
+                    self.create_name_buffer = "New File.txt".into();
+                }
+                if i.modifiers.shift && i.key_pressed(Key::N) {
+                    self.create_dialog = Some((CreateKind::Folder, self.current_path.clone()));
+                    self.create_name_buffer = "New Folder".into();
+                }
+            }
+            s
+        });
+
+        let (go_home, go_root) = ctx.input(|i| {
+            let alt_home = i.modifiers.alt && i.key_pressed(Key::Home);
+            (
+                alt_home && !i.modifiers.shift,
+                alt_home && i.modifiers.shift,
+            )
+        });
+        // Taken before the browser sees them, so Enter doesn't also open the selection.
+        if self.confirm_trash.is_some() {
+            let (enter, escape) = ctx.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, Key::Enter),
+                    i.consume_key(egui::Modifiers::NONE, Key::Escape),
+                )
+            });
+            if enter || escape {
+                self.finish_trash_confirm(enter);
+            }
+        }
+        // Text fields keep their own undo/redo.
+        if !ctx.wants_keyboard_input() {
+            let (redo, undo) = ctx.input_mut(|i| {
+                let redo_shift = egui::Modifiers::COMMAND | egui::Modifiers::SHIFT;
+                let redo = i.consume_key(redo_shift, Key::Z)
+                    || i.consume_key(egui::Modifiers::COMMAND, Key::Y);
+                (redo, i.consume_key(egui::Modifiers::COMMAND, Key::Z))
+            });
+            if redo {
+                self.try_redo();
+            } else if undo {
+                self.try_undo();
+            }
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::H)) {
+            self.browser.show_hidden = !self.browser.show_hidden;
+            self.browser.invalidate();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::L)) {
+            self.editing_path = true;
+            self.focus_path_edit = true;
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, Key::B)) {
+            self.palette = match self.palette {
+                Some(_) => None,
+                None => Some(Palette::default()),
+            };
+        }
+        if go_home {
+            self.go_home();
+        }
+        if go_root {
+            self.go_root();
+        }
+
+        TopBottomPanel::top("titlebar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        self.nav_hist.can_back() || matches!(self.mode, ViewMode::Drives { .. }),
+                        Button::new("⮌"),
+                    )
+                    .clicked()
+                {
+                    self.back();
+                }
+                if ui
+                    .add_enabled(self.nav_hist.can_forward(), Button::new("⮎"))
+                    .clicked()
+                {
+                    self.forward();
+                }
+
+                if ui
+                    .add_enabled(self.can_go_up(), Button::new("⬆"))
+                    .on_disabled_hover_text("Already at the top level")
+                    .clicked()
+                {
+                    self.go_up();
+                }
+                if ui.button("🏠").on_hover_text("Home (Alt+Home)").clicked() {
+                    self.go_home();
+                }
+                if ui
+                    .button("💻")
+                    .on_hover_text("Filesystem root (Alt+Shift+Home)")
+                    .clicked()
+                {
+                    self.go_root();
+                }
+
+                let mut go_recent = None::<PathBuf>;
+                ui.menu_button("⏷", |ui| {
+                    let mut any = false;
+                    for p in self.recent_dirs.iter().filter(|p| p.is_dir()) {
+                        any = true;
+                        let current = *p == self.current_path;
+                        if ui
+                            .selectable_label(current, p.display().to_string())
+                            .clicked()
+                        {
+                            go_recent = Some(p.clone());
+                            ui.close_menu();
+                        }
+                    }
+                    if !any {
+                        ui.weak("No recent locations");
+                    }
+                })
+                .response
+                .on_hover_text("Recent locations");
+                if let Some(p) = go_recent {
+                    self.navigate_to(p);
+                }
+
+                if ui
+                    .selectable_label(self.editing_path, "✏")
+                    .on_hover_text("Edit path (Ctrl+L)")
+                    .clicked()
+                {
+                    self.editing_path = !self.editing_path;
+                    self.focus_path_edit = self.editing_path;
+                }
+                if self.editing_path {
+                    let resp =
+                        ui.add(TextEdit::singleline(&mut self.path_edit).desired_width(400.0));
+                    if std::mem::take(&mut self.focus_path_edit) {
+                        resp.request_focus();
+                    }
+                    if resp.changed() {
+                        self.update_autocomplete();
+                    }
+                    let enter = resp.lost_focus()
+                        && ui.input_mut(|i| i.consume_key(egui::Modifiers::NONE, Key::Enter));
+                    if resp.lost_focus() || enter {
+                        self.autocomplete.clear();
+                        self.editing_path = false;
+                    }
+                    if enter {
+                        self.navigate_to(PathBuf::from(self.path_edit.clone()));
+                    }
+                } else {
+                    self.breadcrumbs(ui);
+                }
+
                 if !self.autocomplete.is_empty() {
                     egui::Frame::popup(ui.style()).show(ui, |ui| {
                         for s in self.autocomplete.clone() {
@@ -354,26 +1547,353 @@ impl eframe::App for AppData {
 
                 ui.separator();
 
-                ui.add(
-                    TextEdit::singleline(&mut self.search_query).hint_text("Search file name..."),
+                let hint = match self.search_options.kind {
+                    searcher::SearchKind::NameOnly => "Search file name...",
+                    _ => "Search files...",
+                };
+                let resp = ui.add(TextEdit::singleline(&mut self.search_query).hint_text(hint));
+                if resp.changed() {
+                    self.search_edited_at = Some(Instant::now());
+                }
+                let history_id = ui.make_persistent_id("search-history");
+                if resp.has_focus()
+                    && self.search_query.is_empty()
+                    && !self.search_history.is_empty()
+                {
+                    ui.memory_mut(|m| m.open_popup(history_id));
+                } else if !self.search_query.is_empty()
+                    && ui.memory(|m| m.is_popup_open(history_id))
+                {
+                    ui.memory_mut(|m| m.close_popup());
+                }
+                let mut rerun = None::<String>;
+                egui::popup_below_widget(
+                    ui,
+                    history_id,
+                    &resp,
+                    egui::PopupCloseBehavior::CloseOnClickOutside,
+                    |ui| {
+                        ui.set_min_width(resp.rect.width());
+                        for query in &self.search_history {
+                            if ui.selectable_label(false, query).clicked() {
+                                rerun = Some(query.clone());
+                            }
+                        }
+                    },
                 );
+                if let Some(query) = rerun {
+                    ui.memory_mut(|m| m.close_popup());
+                    self.search_query = query;
+                    self.search_edited_at = None;
+                    self.start_search(false);
+                }
                 if ui.button("🔍").clicked() {
-                    self.start_search();
+                    self.search_edited_at = None;
+                    self.start_search(false);
+                }
+                ui.checkbox(&mut self.search_options.whole_word, "Whole word")
+                    .on_hover_text("Only match the query between word boundaries");
+                egui::ComboBox::from_id_salt("query-mode")
+                    .selected_text(self.search_options.mode.label())
+                    .width(60.0)
+                    .show_ui(ui, |ui| {
+                        for m in searcher::QueryMode::ALL {
+                            ui.selectable_value(&mut self.search_options.mode, m, m.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text(
+                        "Text matches a substring, Glob takes patterns like *.rs or src/**/*.rs, \
+                         Regex takes a case-insensitive regular expression",
+                    );
+                egui::ComboBox::from_id_salt("search-kind")
+                    .selected_text(self.search_options.kind.label())
+                    .show_ui(ui, |ui| {
+                        for k in searcher::SearchKind::ALL {
+                            ui.selectable_value(&mut self.search_options.kind, k, k.label());
+                        }
+                    });
+                ui.toggle_value(&mut self.show_search_filters, "Filters");
+                ui.toggle_value(&mut self.show_preview, "👁 Preview");
+
+                let mut history_action = None::<bool>;
+                ui.menu_button("🕘 History", |ui| {
+                    if self.ops_hist.undo.is_empty() && self.ops_hist.redo.is_empty() {
+                        ui.weak("No operations yet");
+                    }
+                    // Undone operations sit above the current point, like in an editor.
+                    let redo = &self.ops_hist.redo;
+                    let start = redo.len().saturating_sub(HISTORY_SHOWN);
+                    for (i, op) in redo.iter().enumerate().skip(start) {
+                        let label = RichText::new(format!("⟳ {}", op.describe())).weak();
+                        let next = i + 1 == redo.len();
+                        if ui
+                            .add_enabled(next, Button::new(label).frame(false))
+                            .clicked()
+                        {
+                            history_action = Some(false);
+                            ui.close_menu();
+                        }
+                    }
+                    if !self.ops_hist.redo.is_empty() && !self.ops_hist.undo.is_empty() {
+                        ui.separator();
+                    }
+                    for (i, op) in self
+                        .ops_hist
+                        .undo
+                        .iter()
+                        .rev()
+                        .enumerate()
+                        .take(HISTORY_SHOWN)
+                    {
+                        let label = format!("⟲ {}", op.describe());
+                        if ui
+                            .add_enabled(i == 0, Button::new(label).frame(false))
+                            .clicked()
+                        {
+                            history_action = Some(true);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                match history_action {
+                    Some(true) => self.try_undo(),
+                    Some(false) => self.try_redo(),
+                    None => {}
                 }
 
                 if ui.button("↻").clicked() {
                     self.browser.invalidate();
                 }
+
+                ui.menu_button("☰", |ui| {
+                    if ui.button("Reset zoom (Ctrl+0)").clicked() {
+                        self.scale_factor = self.scale_bounds.clamp(1.0);
+                        ui.close_menu();
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Auto-refresh every");
+                        ui.add(
+                            egui::DragValue::new(&mut self.auto_refresh_secs)
+                                .range(0..=3600)
+                                .suffix(" s"),
+                        )
+                        .on_hover_text("0 turns auto-refresh off");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Purge REX trash older than");
+                        ui.add(
+                            egui::DragValue::new(&mut self.trash_retention_days)
+                                .range(0..=3650)
+                                .suffix(" days"),
+                        )
+                        .on_hover_text(
+                            "Checked at startup; 0 keeps items forever. The system trash is left alone.",
+                        );
+                    });
+                    if ui.button("⬆ Export settings...").clicked() {
+                        self.settings_dialog = Some(SettingsAction::Export);
+                        self.settings_path_buffer =
+                            config::default_bundle_path().display().to_string();
+                        ui.close_menu();
+                    }
+                    if ui.button("⬇ Import settings...").clicked() {
+                        self.settings_dialog = Some(SettingsAction::Import);
+                        self.settings_path_buffer =
+                            config::default_bundle_path().display().to_string();
+                        ui.close_menu();
+                    }
+                    if ui.button("🔍 Find duplicates here...").clicked() {
+                        self.find_duplicates();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("🗑 Empty REX trash...")
+                        .on_hover_text(
+                            "Items replaced by overwrites, or deleted when the system trash \
+                             was unavailable. The system trash is left alone.",
+                        )
+                        .clicked()
+                    {
+                        self.request_empty_trash();
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.confirm_deletes, "Confirm before moving to trash");
+                    ui.checkbox(&mut self.preserve_times, "Preserve timestamps on copy")
+                        .on_hover_text("Copies keep the original modified and accessed times");
+                    ui.checkbox(&mut self.quiet_auto_rename, "Summarize auto-renamed pastes")
+                        .on_hover_text(
+                            "After a paste, list items renamed to avoid a name collision",
+                        );
+                    ui.menu_button("Low disk warning", |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Below");
+                            ui.add(
+                                egui::DragValue::new(&mut self.low_space.min_mb)
+                                    .range(0..=1_048_576)
+                                    .suffix(" MB"),
+                            );
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("or below");
+                            ui.add(
+                                egui::DragValue::new(&mut self.low_space.min_percent)
+                                    .range(0..=100)
+                                    .suffix(" %"),
+                            );
+                        });
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Parallel disk reads");
+                        if ui
+                            .add(egui::DragValue::new(&mut self.max_io_concurrency).range(1..=64))
+                            .on_hover_text(
+                                "Lower this for slow network drives (SMB/NFS). \
+                                 Defaults to the number of CPU threads.",
+                            )
+                            .changed()
+                        {
+                            self.browser.set_io_concurrency(self.max_io_concurrency);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Skip content search above");
+                        let mut mb = self.search_options.max_content_bytes / (1024 * 1024);
+                        if ui
+                            .add(egui::DragValue::new(&mut mb).range(1..=4096).suffix(" MB"))
+                            .changed()
+                        {
+                            self.search_options.max_content_bytes = mb * 1024 * 1024;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Stop searching after");
+                        ui.add(
+                            egui::DragValue::new(&mut self.search_options.max_results)
+                                .range(1..=1_000_000)
+                                .suffix(" results"),
+                        );
+                    });
+                    ui.menu_button("Theme", |ui| {
+                        for theme in config::Theme::ALL {
+                            ui.radio_value(&mut self.theme, theme, theme.label());
+                        }
+                    });
+                    ui.menu_button("Icons", |ui| {
+                        for set in browser::IconSet::ALL {
+                            ui.radio_value(&mut self.browser.icon_set, set, set.label());
+                        }
+                    });
+                    ui.checkbox(&mut self.delete_permanently, "Delete skips the trash")
+                        .on_hover_text(
+                            "Delete permanently by default; Shift+Delete always does, after asking",
+                        );
+                    ui.checkbox(&mut self.warn_extension_change, "Warn on extension change")
+                        .on_hover_text("Ask before a rename changes a file's extension");
+                });
             });
+            if self.show_search_filters {
+                ui.horizontal(|ui| {
+                    ui.label("Extensions");
+                    ui.add(
+                        TextEdit::singleline(&mut self.search_extensions)
+                            .hint_text("rs,toml,md")
+                            .desired_width(120.0),
+                    );
+                    ui.separator();
+                    ui.label("Size");
+                    ui.add(
+                        TextEdit::singleline(&mut self.search_min_size)
+                            .hint_text("min")
+                            .desired_width(60.0),
+                    );
+                    ui.label("–");
+                    ui.add(
+                        TextEdit::singleline(&mut self.search_max_size)
+                            .hint_text("max")
+                            .desired_width(60.0),
+                    )
+                    .on_hover_text("Sizes like 500KB, 10MB or 2.5GB");
+                    ui.separator();
+                    ui.label("Modified");
+                    let selected = match self.search_modified_days {
+                        _ if self.search_modified_custom => "Custom",
+                        None => "Any time",
+                        Some(1) => "Last 24 hours",
+                        Some(7) => "Last 7 days",
+                        Some(30) => "Last 30 days",
+                        Some(_) => "Custom",
+                    };
+                    egui::ComboBox::from_id_salt("search-modified")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            for (days, label) in [
+                                (None, "Any time"),
+                                (Some(1), "Last 24 hours"),
+                                (Some(7), "Last 7 days"),
+                                (Some(30), "Last 30 days"),
+                            ] {
+                                let active = !self.search_modified_custom
+                                    && self.search_modified_days == days;
+                                if ui.selectable_label(active, label).clicked() {
+                                    self.search_modified_days = days;
+                                    self.search_modified_custom = false;
+                                }
+                            }
+                            if ui
+                                .selectable_label(self.search_modified_custom, "Custom")
+                                .clicked()
+                            {
+                                self.search_modified_custom = true;
+                                self.search_modified_days.get_or_insert(14);
+                            }
+                        });
+                    if self.search_modified_custom
+                        && let Some(days) = &mut self.search_modified_days
+                    {
+                        ui.add(egui::DragValue::new(days).range(1..=36500).suffix(" days"));
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.search_options.skip_hidden, "Skip hidden")
+                        .on_hover_text("Ignore hidden files and don't descend into hidden folders");
+                    ui.checkbox(
+                        &mut self.search_options.respect_gitignore,
+                        "Respect .gitignore",
+                    )
+                    .on_hover_text("Leave out files ignored by git, and .git folders");
+                    ui.checkbox(&mut self.search_options.follow_symlinks, "Follow symlinks");
+                    ui.separator();
+                    let mut limit_depth = self.search_options.max_depth.is_some();
+                    if ui
+                        .checkbox(&mut limit_depth, "Max depth")
+                        .on_hover_text("Subfolder levels to search; 0 stays in this folder")
+                        .changed()
+                    {
+                        self.search_options.max_depth = limit_depth.then_some(3);
+                    }
+                    if let Some(depth) = &mut self.search_options.max_depth {
+                        ui.add(egui::DragValue::new(depth).range(0..=64));
+                    }
+                });
+            }
         });
 
         let pinned = self.pinned.clone();
+        let dropped_files: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        let hovering_files = ctx.input(|i| !i.raw.hovered_files.is_empty());
         egui::SidePanel::left("sidebar")
             .resizable(false)
             .default_width(170.0)
             .show(ctx, |ui| {
                 ui.heading("📌 Pinned");
                 let mut to_unpin = None::<PathBuf>;
+                let mut drop_into = None::<(Vec<PathBuf>, clipboard::Mode, PathBuf)>;
                 for p in pinned {
                     let name = p
                         .file_name()
@@ -381,11 +1901,32 @@ impl eframe::App for AppData {
                         .unwrap_or_default()
                         .to_string_lossy()
                         .to_string();
-                    let r = ui.button(name);
+                    let label = match self.tags.get(&p) {
+                        Some(tag) => format!("{name} {tag}"),
+                        None => name,
+                    };
+                    let mut r = ui.button(label);
                     if r.clicked() {
                         self.navigate_to(p.clone());
                     }
+                    if (hovering_files && r.contains_pointer())
+                        || r.dnd_hover_payload::<PathBuf>().is_some()
+                    {
+                        r = r.highlight();
+                    }
+                    if let Some(src) = r.dnd_release_payload::<PathBuf>() {
+                        drop_into = Some((vec![(*src).clone()], clipboard::Mode::Cut, p.clone()));
+                    } else if !dropped_files.is_empty() && r.contains_pointer() {
+                        drop_into = Some((dropped_files.clone(), clipboard::Mode::Copy, p.clone()));
+                    }
                     r.context_menu(|ui| {
+                        if ui
+                            .add_enabled(self.clipboard.has_items(), Button::new("📋 Paste here"))
+                            .clicked()
+                        {
+                            self.paste_into(&p);
+                            ui.close_menu();
+                        }
                         if ui.button("❌ Unpin").clicked() {
                             to_unpin = Some(p.clone());
                             ui.close_menu();
@@ -401,8 +1942,92 @@ impl eframe::App for AppData {
                 if let Some(up) = to_unpin {
                     self.pinned.retain(|x| x != &up);
                 }
+                if let Some((items, mode, target)) = drop_into {
+                    self.transfer(items, mode, &target);
+                }
+
+                let mut all_tags: Vec<String> = self.tags.values().cloned().collect();
+                all_tags.sort();
+                all_tags.dedup();
+                if self
+                    .tag_filter
+                    .as_ref()
+                    .is_some_and(|t| !all_tags.contains(t))
+                {
+                    self.tag_filter = None;
+                }
+                if !all_tags.is_empty() {
+                    ui.separator();
+                    ui.heading("🏷 Tags");
+                    ui.horizontal_wrapped(|ui| {
+                        for tag in all_tags {
+                            let active = self.tag_filter.as_ref() == Some(&tag);
+                            if ui.selectable_label(active, &tag).clicked() {
+                                self.tag_filter = if active { None } else { Some(tag) };
+                            }
+                        }
+                    });
+                    if let Some(filter) = self.tag_filter.clone() {
+                        let mut tagged: Vec<PathBuf> = self
+                            .tags
+                            .iter()
+                            .filter(|(_, t)| **t == filter)
+                            .map(|(p, _)| p.clone())
+                            .collect();
+                        tagged.sort();
+                        for p in tagged {
+                            let name = p
+                                .file_name()
+                                .unwrap_or(p.as_os_str())
+                                .to_string_lossy()
+                                .to_string();
+                            if ui
+                                .button(name)
+                                .on_hover_text(p.display().to_string())
+                                .clicked()
+                            {
+                                self.navigate_to(p);
+                            }
+                        }
+                    }
+                }
+
+                if cfg!(target_os = "windows") {
+                    ui.separator();
+                    ui.heading("🖧 Network");
+                    let mut forget = None::<PathBuf>;
+                    for share in self.network_shares.clone() {
+                        let r = ui.button(share.display().to_string());
+                        if r.clicked() {
+                            self.navigate_to(share.clone());
+                        }
+                        r.context_menu(|ui| {
+                            if ui.button("❌ Forget").clicked() {
+                                forget = Some(share.clone());
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                    if let Some(share) = forget {
+                        self.network_shares.retain(|s| s != &share);
+                    }
+                    if ui.button("🔌 Connect to server...").clicked() {
+                        self.connect_dialog = Some(String::new());
+                    }
+                }
             });
 
+        if self.show_preview {
+            let target = match self.mode {
+                ViewMode::Browsing => self.browser.selected_entry(),
+                _ => None,
+            };
+            egui::SidePanel::right("preview")
+                .resizable(true)
+                .default_width(320.0)
+                .show(ctx, |ui| self.preview.ui(ui, target));
+        }
+
         egui::CentralPanel::default().show(ctx, |ui| {
             if let ViewMode::Searching {
                 results,
@@ -410,80 +2035,275 @@ impl eframe::App for AppData {
                 rx_prog,
                 scanned_files,
                 scanned_dirs,
+                truncated,
                 done,
                 started_at,
+                elapsed,
                 ..
             } = &mut self.mode
             {
                 while let Ok(m) = rx_results.try_recv() {
-                    results.push(m.path);
+                    results.push(m);
                 }
                 while let Ok(p) = rx_prog.try_recv() {
                     *scanned_files = p.scanned_files;
                     *scanned_dirs = p.scanned_dirs;
-                    if p.done {
+                    *truncated |= p.truncated;
+                    if p.done && !*done {
                         *done = true;
+                        *elapsed = started_at.elapsed();
                     }
                 }
+                if !*done {
+                    *elapsed = started_at.elapsed();
+                }
 
-                let results_snapshot: Vec<PathBuf> = results.clone();
+                let results_snapshot: Vec<searcher::SearchMsg> = results.clone();
                 let sf = *scanned_files;
                 let sd = *scanned_dirs;
                 let dn = *done;
-                let st = *started_at;
+                let tr = *truncated;
+                let secs = elapsed.as_secs_f64();
 
                 let mut cancel_requested = false;
-                let mut navigate_to: Option<PathBuf> = None;
+                let mut clicked: Option<(PathBuf, ResultClick)> = None;
+                let mut result_click = self.result_click;
+                let mut results_sort = self.results_sort;
+                let mut results_filter = self.results_filter.clone();
+                let mut results_filter_regex = self.results_filter_regex;
 
                 ui.horizontal(|ui| {
-                    let elapsed = st.elapsed().as_secs_f32();
-                    let val = if dn {
-                        1.0
+                    if dn {
+                        ui.label("✔");
+                    } else {
+                        ui.spinner();
+                    }
+                    let rate = if secs > 0.0 { sf as f64 / secs } else { 0.0 };
+                    ui.label(format!("{secs:.1} s  •  {rate:.0} files/s"))
+                        .on_hover_text(if dn {
+                            "Total time and average rate"
+                        } else {
+                            "Time so far and average rate"
+                        });
+                    let found = if tr {
+                        format!("showing first {} (truncated)", results_snapshot.len())
                     } else {
-                        ((elapsed * 0.4).sin() * 0.5 + 0.5).clamp(0.05, 0.95)
+                        results_snapshot.len().to_string()
                     };
-                    ui.add(ProgressBar::new(val).show_percentage());
                     ui.label(format!(
-                        "Scanned: {sf} files in {sd} folders  •  Results: {}",
-                        results_snapshot.len()
+                        "Scanned: {sf} files in {sd} folders  •  Results: {found}"
                     ));
                     if ui.button("❌ Cancel").clicked() {
                         cancel_requested = true;
                     }
+                    egui::ComboBox::from_id_salt("result-click")
+                        .selected_text(result_click.label())
+                        .show_ui(ui, |ui| {
+                            for c in ResultClick::ALL {
+                                ui.selectable_value(&mut result_click, c, c.label());
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Click action. Ctrl+click opens, Shift+click keeps results.",
+                        );
+                });
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("result-sort")
+                        .selected_text(format!("Sort: {}", results_sort.label()))
+                        .show_ui(ui, |ui| {
+                            for k in ResultSort::ALL {
+                                ui.selectable_value(&mut results_sort, k, k.label());
+                            }
+                        });
+                    ui.add(
+                        TextEdit::singleline(&mut results_filter)
+                            .hint_text("Refine results...")
+                            .desired_width(200.0),
+                    );
+                    ui.checkbox(&mut results_filter_regex, "Regex")
+                        .on_hover_text("Refine with a regular expression instead of words");
+                    if ui.button("✖ Clear").clicked() {
+                        results_sort = ResultSort::Found;
+                        results_filter.clear();
+                    }
                 });
                 ui.separator();
 
-                egui::ScrollArea::vertical().show(ui, |ui| {
-                    for path in &results_snapshot {
-                        if ui.button(path.display().to_string()).clicked() {
-                            navigate_to = Some(path.clone());
-                        }
+                let filter = results_filter.to_lowercase();
+                let terms: Vec<&str> = filter.split_whitespace().collect();
+                let refine_re = if results_filter_regex && !terms.is_empty() {
+                    if self
+                        .results_filter_re
+                        .as_ref()
+                        .is_none_or(|(text, _)| *text != results_filter)
+                    {
+                        let built = RegexBuilder::new(&results_filter)
+                            .case_insensitive(true)
+                            .build()
+                            .ok();
+                        self.results_filter_re = Some((results_filter.clone(), built));
+                    }
+                    let built = self
+                        .results_filter_re
+                        .as_ref()
+                        .and_then(|(_, re)| re.as_ref());
+                    if built.is_none() {
+                        ui.colored_label(ui.visuals().error_fg_color, "Invalid regex");
+                    }
+                    built
+                } else {
+                    None
+                };
+                let mut shown: Vec<&searcher::SearchMsg> = results_snapshot
+                    .iter()
+                    .filter(|m| {
+                        let text = m.path.display().to_string();
+                        match &refine_re {
+                            Some(re) => re.is_match(&text),
+                            None if results_filter_regex => true,
+                            None => {
+                                let text = text.to_lowercase();
+                                terms.iter().all(|t| text.contains(t))
+                            }
+                        }
+                    })
+                    .collect();
+                if !terms.is_empty() {
+                    ui.weak(format!(
+                        "Showing {} of {} results",
+                        shown.len(),
+                        results_snapshot.len()
+                    ));
+                }
+                match results_sort {
+                    ResultSort::Found => {}
+                    ResultSort::Name => shown.sort_by_cached_key(|m| {
+                        (
+                            m.path
+                                .file_name()
+                                .unwrap_or_default()
+                                .to_string_lossy()
+                                .to_lowercase(),
+                            m.path.clone(),
+                        )
+                    }),
+                    ResultSort::Path => shown.sort_by(|a, b| a.path.cmp(&b.path)),
+                    ResultSort::Depth => shown.sort_by(|a, b| {
+                        (a.path.components().count(), &a.path)
+                            .cmp(&(b.path.components().count(), &b.path))
+                    }),
+                    ResultSort::Size => {
+                        shown.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)))
+                    }
+                    ResultSort::Modified => shown.sort_by(|a, b| {
+                        b.modified
+                            .cmp(&a.modified)
+                            .then_with(|| a.path.cmp(&b.path))
+                    }),
+                }
+                self.results_sort = results_sort;
+                self.results_filter = results_filter;
+                self.results_filter_regex = results_filter_regex;
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for m in shown {
+                        let path = &m.path;
+                        let label = highlighted_path(ui, path, &m.name_match);
+                        if ui.button(label).clicked() {
+                            let action = ui.input(|i| {
+                                if i.modifiers.command {
+                                    ResultClick::Open
+                                } else if i.modifiers.shift {
+                                    ResultClick::NavigateKeepOpen
+                                } else {
+                                    result_click
+                                }
+                            });
+                            clicked = Some((path.to_path_buf(), action));
+                        }
+                        if let Some((n, text)) = &m.line {
+                            ui.weak(format!("    {n}: {text}"));
+                        }
                     }
                 });
+                self.result_click = result_click;
 
                 if cancel_requested {
                     self.cancel_search();
                 }
-                if let Some(p) = navigate_to {
-                    if let Some(dir) = p.parent() {
-                        self.navigate_to(dir.to_path_buf());
+                if let Some((p, action)) = clicked {
+                    match action {
+                        ResultClick::Open => platform::open_file(&p),
+                        ResultClick::Navigate | ResultClick::NavigateKeepOpen => {
+                            if let Some(dir) = p.parent() {
+                                self.navigate_to(dir.to_path_buf());
+                            }
+                            if action == ResultClick::Navigate {
+                                self.mode = ViewMode::Browsing;
+                            }
+                        }
                     }
-                    self.mode = ViewMode::Browsing;
+                }
+            } else if let ViewMode::Drives { drives } = &self.mode {
+                let mut open_drive = None;
+                ui.heading("This PC");
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for drive in drives {
+                        let label = format!("🖴 {}", drive.display());
+                        if ui.selectable_label(false, label).double_clicked() {
+                            open_drive = Some(drive.clone());
+                        }
+                    }
+                    if drives.is_empty() {
+                        ui.label("No drives found.");
+                    }
+                });
+                if let Some(drive) = open_drive {
+                    self.navigate_to(drive);
                 }
             } else {
                 let mut on_open = None::<PathBuf>;
                 let mut on_pin = None::<PathBuf>;
                 let mut on_rename = None::<(PathBuf, String)>;
-                let mut on_delete = None::<PathBuf>;
+                let mut on_delete = None::<(Vec<PathBuf>, bool)>;
                 let mut on_open_with = None::<PathBuf>;
+                let mut on_extract = None::<PathBuf>;
+                let mut on_compress = None::<Vec<PathBuf>>;
+                let mut on_hash = None::<(Vec<PathBuf>, fs_ops::HashKind)>;
                 let mut on_open_term = None::<PathBuf>;
 
-                let mut on_copy_req = None::<PathBuf>;
-                let mut on_cut_req = None::<PathBuf>;
+                let mut on_copy_req = None::<Vec<PathBuf>>;
+                let mut on_cut_req = None::<Vec<PathBuf>>;
+                let mut on_copy_structure_req = None::<PathBuf>;
                 let mut on_paste_here = None::<PathBuf>;
+                let mut on_symlink_here = None::<PathBuf>;
                 let mut on_undo_req = false;
+                let mut on_redo_req = false;
+                let undo_hint = self.ops_hist.next_undo().map(fs_ops::Op::describe);
+                let redo_hint = self.ops_hist.next_redo().map(fs_ops::Op::describe);
+                let mut on_go_up = false;
                 let mut on_new_folder_here = None::<PathBuf>;
                 let mut on_new_file_here = None::<PathBuf>;
+                let mut on_flatten_req = None::<PathBuf>;
+                let mut on_mtime_req = None::<PathBuf>;
+                let mut on_permissions_req = None::<PathBuf>;
+                let mut on_duplicate_req = None::<PathBuf>;
+                let mut on_tag_req = None::<PathBuf>;
+                let mut on_drag_out = None::<PathBuf>;
+                let mut on_folder_size = None::<(PathBuf, fs_ops::DirSize)>;
+                let mut on_copy_text = None::<(String, &str)>;
+
+                self.item_errors
+                    .retain(|(_, _, at)| at.elapsed() < Duration::from_secs(6));
+                let error_paths: HashMap<PathBuf, String> = self
+                    .item_errors
+                    .iter()
+                    .map(|(p, reason, _)| (p.clone(), reason.clone()))
+                    .collect();
+                if !error_paths.is_empty() {
+                    ctx.request_repaint_after(Duration::from_millis(500));
+                }
 
                 self.browser.update(
                     ctx,
@@ -494,19 +2314,52 @@ impl eframe::App for AppData {
                     &mut on_rename,
                     &mut on_delete,
                     &mut on_open_with,
+                    &mut on_extract,
+                    &mut on_compress,
+                    &mut on_hash,
                     &mut on_open_term,
                     &mut on_copy_req,
                     &mut on_cut_req,
+                    &mut on_copy_structure_req,
                     &mut on_paste_here,
+                    &mut on_symlink_here,
                     &mut on_undo_req,
+                    &mut on_redo_req,
+                    undo_hint.as_deref(),
+                    redo_hint.as_deref(),
+                    &mut on_go_up,
                     self.clipboard.has_items(),
                     &mut on_new_folder_here,
                     &mut on_new_file_here,
+                    &mut on_flatten_req,
+                    &mut on_mtime_req,
+                    &mut on_permissions_req,
+                    &mut on_duplicate_req,
+                    &mut on_tag_req,
+                    &mut on_drag_out,
+                    &mut on_folder_size,
+                    &mut on_copy_text,
+                    &error_paths,
+                    &self.tags,
                 );
 
                 if let Some(nav) = on_open {
                     self.navigate_to(nav);
                 }
+                if on_go_up {
+                    self.go_up();
+                }
+                if let Some((dir, size)) = on_folder_size {
+                    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+                    let total = fs_ops::format_size(size.bytes);
+                    if size.partial {
+                        self.toasts.warn(format!(
+                            "{name} is at least {total} (some folders could not be read)"
+                        ));
+                    } else {
+                        self.toasts.info(format!("{name} is {total}"));
+                    }
+                }
                 if let Some(pin) = on_pin {
                     if !self.pinned.contains(&pin) {
                         self.pinned.push(pin);
@@ -516,46 +2369,75 @@ impl eframe::App for AppData {
                     }
                 }
                 if let Some((from, new_name)) = on_rename {
-                    match fs_ops::rename(&from, &new_name) {
-                        Ok(op) => {
-                            self.ops_hist.push(op);
-                            self.browser.invalidate();
-                        }
-                        Err(e) => self.toasts.error(format!("Rename failed: {e}")),
+                    if self.warn_extension_change && Self::changes_extension(&from, &new_name) {
+                        self.pending_rename = Some((from, new_name));
+                    } else {
+                        self.apply_rename(&from, &new_name, false);
                     }
                 }
-                if let Some(p) = on_delete {
-                    match fs_ops::delete_to_trash(&p) {
-                        Ok(op) => {
-                            self.ops_hist.push(op);
-                            self.browser.invalidate();
-                            self.toasts.info("Moved to trash.");
-                        }
-                        Err(e) => self.toasts.error(format!("Delete failed: {e}")),
+                if let Some((paths, shift)) = on_delete {
+                    if let Some(next) = paths
+                        .iter()
+                        .filter_map(|p| self.browser.neighbour_of(p))
+                        .find(|n| !paths.contains(n))
+                    {
+                        self.browser.select_after_reload(next);
+                    }
+                    if shift || self.delete_permanently {
+                        self.confirm_permanent_delete = Some(paths);
+                    } else {
+                        self.request_trash(paths);
                     }
                 }
                 if let Some(p) = on_open_with {
+                    self.open_with_buffer = self
+                        .open_with_last
+                        .get(&Self::open_with_key(&p))
+                        .cloned()
+                        .unwrap_or_default();
                     self.open_with_target = Some(p);
-                    self.open_with_buffer.clear();
                 }
                 if let Some(p) = on_open_term {
                     platform::open_terminal_in(&p);
                 }
 
-                if let Some(p) = on_copy_req {
-                    self.clipboard.set(vec![p], clipboard::Mode::Copy);
+                if let Some((text, confirmation)) = on_copy_text {
+                    clipboard::set_text(ctx, text);
+                    self.toasts.info(confirmation);
+                }
+                if let Some(paths) = on_copy_req {
+                    self.clipboard.set(paths, clipboard::Mode::Copy);
                     self.toasts.info("Copied to buffer");
                 }
-                if let Some(p) = on_cut_req {
-                    self.clipboard.set(vec![p], clipboard::Mode::Cut);
+                if let Some(paths) = on_cut_req {
+                    self.clipboard.set(paths, clipboard::Mode::Cut);
                     self.toasts.info("Cut to buffer");
                 }
+                if let Some(p) = on_copy_structure_req {
+                    self.clipboard.set(vec![p], clipboard::Mode::CopyStructure);
+                    self.toasts.info("Folder structure copied to buffer");
+                }
                 if let Some(target_dir) = on_paste_here {
                     self.paste_into(&target_dir);
                 }
+                if let Some(archive) = on_extract {
+                    self.extract(archive);
+                }
+                if let Some((paths, kind)) = on_hash {
+                    self.request_hashes(paths, kind);
+                }
+                if let Some(paths) = on_compress {
+                    self.compress(paths);
+                }
+                if let Some(target_dir) = on_symlink_here {
+                    self.link_into(&target_dir);
+                }
                 if on_undo_req {
                     self.try_undo();
                 }
+                if on_redo_req {
+                    self.try_redo();
+                }
                 if let Some(target_dir) = on_new_folder_here {
                     self.create_dialog = Some((CreateKind::Folder, target_dir));
                     self.create_name_buffer = "New Folder".to_string();
@@ -564,19 +2446,510 @@ impl eframe::App for AppData {
                     self.create_dialog = Some((CreateKind::File, target_dir));
                     self.create_name_buffer = "New File.txt".to_string();
                 }
+                if let Some(dir) = on_flatten_req {
+                    self.flatten_target = Some(dir);
+                }
+                if let Some(p) = on_duplicate_req
+                    && let Some(parent) = p.parent().map(Path::to_path_buf)
+                {
+                    self.transfer(vec![p], clipboard::Mode::Copy, &parent);
+                }
+                if let Some(p) = on_tag_req {
+                    let current = self.tags.get(&p).cloned().unwrap_or_default();
+                    self.tag_dialog = Some((p, current));
+                }
+                if let Some(p) = on_mtime_req {
+                    match fs_ops::modified(&p) {
+                        Ok(t) => {
+                            self.mtime_dialog = Some((p, fs_ops::DateTime::from_system_time(t)))
+                        }
+                        Err(e) => self.toasts.error(format!("Cannot read modified date: {e}")),
+                    }
+                }
+                if let Some(p) = on_permissions_req {
+                    match std::fs::metadata(&p).map(|m| fs_ops::mode_of(&m)) {
+                        Ok(Some(mode)) => self.chmod_dialog = Some((p, mode, format!("{mode:o}"))),
+                        Ok(None) => self.toasts.error("Permissions are not supported here."),
+                        Err(e) => self.toasts.error(format!("Cannot read permissions: {e}")),
+                    }
+                }
+                if let Some(p) = on_drag_out {
+                    egui::DragAndDrop::clear_payload(ctx);
+                    if !platform::start_drag_out(frame, &[p]) {
+                        self.toasts
+                            .error("Could not start dragging the file out of REX.");
+                    }
+                }
             }
 
+            if !self.queue.is_empty() {
+                egui::TopBottomPanel::bottom("op-queue").show_inside(ui, |ui| {
+                    self.queue.draw(ui);
+                });
+            }
             egui::TopBottomPanel::bottom("toasts").show_inside(ui, |ui| {
                 self.toasts.draw(ui);
             });
         });
 
+        if let Some(job) = self.queue.transfer() {
+            egui::Window::new("Transferring")
+                .collapsible(false)
+                .resizable(false)
+                .default_width(400.0)
+                .show(ctx, |ui| {
+                    ui.strong(&job.label);
+                    ui.label(format!("{} of {} item(s) done", job.done, job.total));
+                    let fraction = if job.bytes_total > 0 {
+                        job.bytes_done as f32 / job.bytes_total as f32
+                    } else {
+                        0.0
+                    };
+                    let text = if job.bytes_total > 0 {
+                        format!(
+                            "{} of {}",
+                            fs_ops::format_size(job.bytes_done),
+                            fs_ops::format_size(job.bytes_total)
+                        )
+                    } else {
+                        fs_ops::format_size(job.bytes_done)
+                    };
+                    ui.add(egui::ProgressBar::new(fraction.min(1.0)).text(text));
+                    if let Some(current) = &job.current {
+                        ui.add(egui::Label::new(current.display().to_string()).truncate());
+                    }
+                    if ui.button("Cancel").clicked() {
+                        job.cancel();
+                    }
+                });
+        }
+
+        if let Some(finder) = &mut self.dupe_finder {
+            while let Ok(msg) = finder.rx.try_recv() {
+                match msg {
+                    dupes::DupeMsg::Scanned(n) => finder.scanned = Some(n),
+                    dupes::DupeMsg::Hashed { done, total } => finder.hashed = (done, total),
+                    dupes::DupeMsg::Done(groups) => {
+                        finder.groups = Some(
+                            groups
+                                .into_iter()
+                                .map(|g| {
+                                    // Keep the first copy of each group by default.
+                                    let marked = g.paths.into_iter().enumerate();
+                                    (g.size, marked.map(|(i, p)| (p, i > 0)).collect())
+                                })
+                                .collect(),
+                        )
+                    }
+                }
+            }
+            let mut close = false;
+            let mut trash = None::<Vec<PathBuf>>;
+            egui::Window::new("Duplicate files")
+                .collapsible(false)
+                .default_width(560.0)
+                .show(ctx, |ui| {
+                    ui.label(format!("In {}", finder.root.display()));
+                    let Some(groups) = &mut finder.groups else {
+                        ctx.request_repaint_after(Duration::from_millis(100));
+                        match finder.scanned {
+                            None => {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("Scanning files...");
+                                });
+                            }
+                            Some(scanned) => {
+                                let (done, total) = finder.hashed;
+                                ui.label(format!(
+                                    "Comparing contents of {total} of {scanned} file(s)"
+                                ));
+                                let fraction = if total > 0 {
+                                    done as f32 / total as f32
+                                } else {
+                                    0.0
+                                };
+                                ui.add(egui::ProgressBar::new(fraction).show_percentage());
+                            }
+                        }
+                        close = ui.button("Cancel").clicked();
+                        return;
+                    };
+                    if groups.is_empty() {
+                        ui.label("No duplicate files found.");
+                        close = ui.button("Close").clicked();
+                        return;
+                    }
+                    let marked: Vec<PathBuf> = groups
+                        .iter()
+                        .flat_map(|(_, paths)| paths.iter().filter(|(_, m)| *m))
+                        .map(|(p, _)| p.clone())
+                        .collect();
+                    let reclaimed: u64 = groups
+                        .iter()
+                        .map(|(size, paths)| size * paths.iter().filter(|(_, m)| *m).count() as u64)
+                        .sum();
+                    let keeps_one = groups
+                        .iter()
+                        .all(|(_, paths)| paths.iter().any(|(_, m)| !*m));
+                    ui.label(format!(
+                        "{} group(s) of identical files. Trashing the marked copies frees {}.",
+                        groups.len(),
+                        fs_ops::format_size(reclaimed)
+                    ));
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(400.0)
+                        .show(ui, |ui| {
+                            for (i, (size, paths)) in groups.iter_mut().enumerate() {
+                                egui::CollapsingHeader::new(format!(
+                                    "{} copies of {}",
+                                    paths.len(),
+                                    fs_ops::format_size(*size)
+                                ))
+                                .id_salt(i)
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    let mut keep = None;
+                                    for (j, (p, mark)) in paths.iter_mut().enumerate() {
+                                        ui.horizontal(|ui| {
+                                            ui.checkbox(mark, p.display().to_string());
+                                            if ui.small_button("Keep only this").clicked() {
+                                                keep = Some(j);
+                                            }
+                                        });
+                                    }
+                                    if let Some(keep) = keep {
+                                        for (j, (_, mark)) in paths.iter_mut().enumerate() {
+                                            *mark = j != keep;
+                                        }
+                                    }
+                                });
+                            }
+                        });
+                    ui.separator();
+                    if !keeps_one {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            "Every copy in a group is marked; keep at least one.",
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(
+                                keeps_one && !marked.is_empty(),
+                                Button::new(format!("🗑 Trash {} marked", marked.len())),
+                            )
+                            .clicked()
+                        {
+                            trash = Some(marked);
+                            close = true;
+                        }
+                        if ui.button("Close").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                finder.abort.store(true, Ordering::Relaxed);
+                self.dupe_finder = None;
+            }
+            if let Some(paths) = trash {
+                self.trash_paths(paths);
+            }
+        }
+        if let Some(dialog) = &mut self.hash_dialog {
+            while let Ok((i, res)) = dialog.rx.try_recv() {
+                dialog.results[i].1 = Some(res);
+            }
+            let pending = dialog.results.iter().any(|(_, r)| r.is_none());
+            if pending {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+            let mut close = false;
+            egui::Window::new(format!("{} checksums", dialog.kind.label()))
+                .collapsible(false)
+                .default_width(560.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("hash-results")
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for (p, res) in &dialog.results {
+                                        let name = p.file_name().unwrap_or_default();
+                                        ui.label(name.to_string_lossy())
+                                            .on_hover_text(p.display().to_string());
+                                        match res {
+                                            None => {
+                                                ui.spinner();
+                                            }
+                                            Some(Ok(digest)) => {
+                                                ui.monospace(digest);
+                                                if ui
+                                                    .small_button("📋")
+                                                    .on_hover_text("Copy digest")
+                                                    .clicked()
+                                                {
+                                                    clipboard::set_text(ctx, digest.clone());
+                                                }
+                                            }
+                                            Some(Err(e)) => {
+                                                ui.colored_label(ui.visuals().error_fg_color, e);
+                                            }
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(!pending, Button::new("📋 Copy all"))
+                            .clicked()
+                        {
+                            clipboard::set_text(ctx, dialog.text());
+                        }
+                        if ui.button("Close").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                dialog.abort.store(true, Ordering::Relaxed);
+                self.hash_dialog = None;
+            }
+        }
+        if let Some(report) = &self.batch_report {
+            let mut close = false;
+            egui::Window::new("Operation summary")
+                .collapsible(false)
+                .default_width(480.0)
+                .show(ctx, |ui| {
+                    ui.strong(&report.label);
+                    let status = if report.cancelled { " (cancelled)" } else { "" };
+                    ui.label(format!(
+                        "{} succeeded, {} failed{status}",
+                        report.succeeded.len(),
+                        report.failed.len()
+                    ));
+                    ui.separator();
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            egui::CollapsingHeader::new(format!(
+                                "Failed ({})",
+                                report.failed.len()
+                            ))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for (p, reason) in &report.failed {
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        format!("{}: {reason}", p.display()),
+                                    );
+                                }
+                            });
+                            egui::CollapsingHeader::new(format!(
+                                "Succeeded ({})",
+                                report.succeeded.len()
+                            ))
+                            .show(ui, |ui| {
+                                for p in &report.succeeded {
+                                    ui.label(p.display().to_string());
+                                }
+                            });
+                        });
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("📋 Copy report").clicked() {
+                            ctx.copy_text(report.text());
+                        }
+                        if ui.button("Close").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                self.batch_report = None;
+            }
+        }
+        if let Some(query) = self.palette.as_ref().map(|p| p.query.clone()) {
+            let entries = self.palette_entries(&query);
+            let mut go = None::<PathBuf>;
+            let mut close = false;
+            if let Some(palette) = &mut self.palette {
+                let (up, down, enter, escape) = ctx.input(|i| {
+                    (
+                        i.key_pressed(Key::ArrowUp),
+                        i.key_pressed(Key::ArrowDown),
+                        i.key_pressed(Key::Enter),
+                        i.key_pressed(Key::Escape),
+                    )
+                });
+                if down && palette.selected + 1 < entries.len() {
+                    palette.selected += 1;
+                }
+                if up {
+                    palette.selected = palette.selected.saturating_sub(1);
+                }
+                palette.selected = palette.selected.min(entries.len().saturating_sub(1));
+                egui::Window::new("Jump to")
+                    .collapsible(false)
+                    .resizable(false)
+                    .title_bar(false)
+                    .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+                    .show(ctx, |ui| {
+                        let resp = ui.add(
+                            TextEdit::singleline(&mut palette.query)
+                                .hint_text("Jump to pinned or recent folder...")
+                                .desired_width(400.0),
+                        );
+                        resp.request_focus();
+                        if resp.changed() {
+                            palette.selected = 0;
+                        }
+                        ui.separator();
+                        for (i, (label, path)) in entries.iter().enumerate() {
+                            if ui.selectable_label(i == palette.selected, label).clicked() {
+                                go = Some(path.clone());
+                            }
+                        }
+                        if entries.is_empty() {
+                            ui.weak("No matches");
+                        }
+                    });
+                if enter && let Some((_, path)) = entries.get(palette.selected) {
+                    go = Some(path.clone());
+                }
+                close = escape;
+            }
+            if let Some(path) = go {
+                self.palette = None;
+                self.navigate_to(path);
+            } else if close {
+                self.palette = None;
+            }
+        }
+        if let Some((path, buffer)) = &mut self.tag_dialog {
+            let mut result = None::<Option<String>>;
+            let mut close = false;
+            egui::Window::new("Set tag")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(path.display().to_string());
+                    ui.horizontal_wrapped(|ui| {
+                        for preset in ["🔴", "🟠", "🟡", "🟢", "🔵", "🟣", "⭐", "❤", "🔥", "📌"]
+                        {
+                            if ui
+                                .selectable_label(buffer.as_str() == preset, preset)
+                                .clicked()
+                            {
+                                *buffer = preset.to_string();
+                            }
+                        }
+                    });
+                    ui.add(
+                        TextEdit::singleline(buffer)
+                            .hint_text("Emoji or short label")
+                            .char_limit(16),
+                    );
+                    ui.horizontal(|ui| {
+                        let tag = buffer.trim().to_string();
+                        if ui
+                            .add_enabled(!tag.is_empty(), Button::new("Apply"))
+                            .clicked()
+                        {
+                            result = Some(Some(tag));
+                        }
+                        if ui.button("Remove tag").clicked() {
+                            result = Some(None);
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            if close {
+                self.tag_dialog = None;
+            }
+            if let Some(tag) = result
+                && let Some((path, _)) = self.tag_dialog.take()
+            {
+                match tag {
+                    Some(tag) => self.tags.insert(path, tag),
+                    None => self.tags.remove(&path),
+                };
+            }
+        }
+        if let Some(buffer) = &mut self.connect_dialog {
+            let mut connect = None::<PathBuf>;
+            let mut close = false;
+            egui::Window::new("Connect to server")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Network folder:");
+                    let resp = ui.add(
+                        TextEdit::singleline(buffer)
+                            .hint_text("\\\\server\\share")
+                            .desired_width(300.0),
+                    );
+                    let parsed = platform::parse_unc(buffer);
+                    if parsed.is_none() && !buffer.trim().is_empty() {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            "Enter a path like \\\\server\\share",
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        let enter = resp.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+                        if ui
+                            .add_enabled(parsed.is_some(), Button::new("Connect"))
+                            .clicked()
+                            || enter
+                        {
+                            connect = parsed.map(|(_, full)| full);
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            if let Some(path) = connect {
+                self.connect_dialog = None;
+                self.navigate_to(path);
+            } else if close {
+                self.connect_dialog = None;
+            }
+        }
         if let Some(tgt) = self.open_with_target.clone() {
             egui::Window::new("Open with...")
                 .collapsible(false)
                 .resizable(false)
                 .show(ctx, |ui| {
                     ui.label(format!("File: {}", tgt.display()));
+                    let key = Self::open_with_key(&tgt);
+                    if let Some(last) = self.open_with_last.get(&key).cloned() {
+                        let kind = if key.is_empty() {
+                            "files without an extension".to_string()
+                        } else {
+                            format!(".{key} files")
+                        };
+                        ui.label(format!("Last used for {kind}:"));
+                        if ui.button(format!("▶ Open with {last}")).clicked() {
+                            platform::open_with(&tgt, &last);
+                            self.open_with_target = None;
+                        }
+                        ui.separator();
+                    }
                     ui.horizontal(|ui| {
                         if ui.button("System default").clicked() {
                             platform::open_file(&tgt);
@@ -594,10 +2967,453 @@ impl eframe::App for AppData {
                     );
                     if ui.button("Open").clicked() {
                         platform::open_with(&tgt, &self.open_with_buffer);
+                        let cmd = self.open_with_buffer.trim();
+                        if !cmd.is_empty() {
+                            self.open_with_last
+                                .insert(Self::open_with_key(&tgt), cmd.to_string());
+                        }
                         self.open_with_target = None;
                     }
                 });
         }
+        if let Some(pending) = &mut self.pending_transfer {
+            let mut choice = None::<Option<fs_ops::Conflict>>;
+            let mut cancel = false;
+            let current = pending.conflicts.front().cloned().unwrap_or_default();
+            let remaining = pending.conflicts.len();
+            egui::Window::new("Name conflict")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "\"{}\" already exists in {}.",
+                        current.file_name().unwrap_or_default().to_string_lossy(),
+                        pending.target_dir.display()
+                    ));
+                    if remaining > 1 {
+                        ui.checkbox(
+                            &mut pending.apply_to_all,
+                            format!("Do this for all {remaining} conflicts"),
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Overwrite").clicked() {
+                            choice = Some(Some(fs_ops::Conflict::Overwrite));
+                        }
+                        if ui.button("Skip").clicked() {
+                            choice = Some(None);
+                        }
+                        if ui.button("Keep both").clicked() {
+                            choice = Some(Some(fs_ops::Conflict::KeepBoth));
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+            if cancel {
+                self.pending_transfer = None;
+            } else if let Some(choice) = choice {
+                self.resolve_conflict(choice);
+            }
+        }
+        if let Some(rx) = &self.empty_trash_dialog {
+            if let Ok(summary) = rx.try_recv() {
+                self.trash_summary = Some(summary);
+            }
+            let mut close = false;
+            egui::Window::new("Empty REX trash")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| match self.trash_summary {
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Measuring REX trash...");
+                        });
+                        ctx.request_repaint_after(Duration::from_millis(100));
+                        close = ui.button("Cancel").clicked();
+                    }
+                    Some(summary) if summary.items == 0 => {
+                        ui.label("REX's trash is already empty.");
+                        close = ui.button("Close").clicked();
+                    }
+                    Some(summary) => {
+                        ui.label(format!(
+                            "Permanently delete {} item(s) ({} files, {}) from REX's trash?",
+                            summary.items,
+                            summary.files,
+                            fs_ops::format_size(summary.bytes)
+                        ));
+                        ui.label("Items in the system trash are not affected.");
+                        ui.colored_label(ui.visuals().warn_fg_color, "This cannot be undone.");
+                        ui.horizontal(|ui| {
+                            if ui.button("Empty REX trash").clicked() {
+                                self.empty_trash();
+                                close = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                close = true;
+                            }
+                        });
+                    }
+                });
+            if close {
+                self.empty_trash_dialog = None;
+            }
+        }
+        if let Some(dir) = self.flatten_target.clone() {
+            egui::Window::new("Flatten folder")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Move every file below {} into its top level?",
+                        dir.display()
+                    ));
+                    ui.label("Name collisions get a numbered suffix.");
+                    ui.checkbox(&mut self.flatten_remove_empty, "Delete emptied subfolders");
+                    ui.horizontal(|ui| {
+                        if ui.button("Flatten").clicked() {
+                            let remove_empty = self.flatten_remove_empty;
+                            let dir = dir.clone();
+                            let label = format!("Flatten {}", dir.display());
+                            self.queue.enqueue(label, move |_| {
+                                JobOutcome::single(&dir, fs_ops::flatten(&dir, remove_empty))
+                            });
+                            self.flatten_target = None;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            self.flatten_target = None;
+                        }
+                    });
+                });
+        }
+        if let Some(action) = self.settings_dialog {
+            let title = match action {
+                SettingsAction::Export => "Export settings",
+                SettingsAction::Import => "Import settings",
+            };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("Settings file:");
+                    ui.add(
+                        TextEdit::singleline(&mut self.settings_path_buffer).desired_width(360.0),
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button(title).clicked() {
+                            let path = PathBuf::from(self.settings_path_buffer.trim());
+                            match action {
+                                SettingsAction::Export => {
+                                    self.save_settings();
+                                    match config::export_settings(&path) {
+                                        Ok(()) => self.toasts.info(format!(
+                                            "Settings exported to {}",
+                                            path.display()
+                                        )),
+                                        Err(e) => self.toasts.error(format!("Export failed: {e}")),
+                                    }
+                                }
+                                SettingsAction::Import => match config::read_bundle(&path) {
+                                    Ok(bundle) => self.pending_import = Some(bundle),
+                                    Err(e) => self.toasts.error(format!("Import failed: {e}")),
+                                },
+                            }
+                            self.settings_dialog = None;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            self.settings_dialog = None;
+                        }
+                    });
+                });
+        }
+        if let Some(bundle) = &self.pending_import {
+            let summary = format!(
+                "The file has {} setting(s) and {} pin(s).",
+                bundle.prefs.len(),
+                bundle.pinned.len()
+            );
+            egui::Window::new("Import settings")
+                .id(egui::Id::new("import-settings-confirm"))
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(summary);
+                    ui.label("Merge keeps your other settings and pins; replace discards them.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Merge").clicked() {
+                            self.import_settings(false);
+                        }
+                        if ui.button("Replace").clicked() {
+                            self.import_settings(true);
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            self.pending_import = None;
+                        }
+                    });
+                });
+        }
+        if let Some(confirm) = &mut self.confirm_trash {
+            if let Ok(summary) = confirm.rx.try_recv() {
+                confirm.summary = Some(summary);
+            }
+            let mut choice = None;
+            egui::Window::new("Move to trash?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Move {} to the trash?",
+                        Self::describe_items(&confirm.paths)
+                    ));
+                    match confirm.summary {
+                        Some(summary) => {
+                            ui.label(format!(
+                                "{} file(s), {} in total",
+                                summary.files,
+                                fs_ops::format_size(summary.bytes)
+                            ));
+                        }
+                        None => {
+                            ui.horizontal(|ui| {
+                                ui.spinner();
+                                ui.label("Counting files...");
+                            });
+                            ctx.request_repaint_after(Duration::from_millis(100));
+                        }
+                    }
+                    ui.checkbox(&mut confirm.dont_ask, "Don't ask again this session");
+                    ui.horizontal(|ui| {
+                        if ui.button("Move to trash").clicked() {
+                            choice = Some(true);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            choice = Some(false);
+                        }
+                    });
+                });
+            if let Some(confirmed) = choice {
+                self.finish_trash_confirm(confirmed);
+            }
+        }
+        if let Some(paths) = self.confirm_permanent_delete.clone() {
+            egui::Window::new("Delete permanently?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let what = Self::describe_items(&paths);
+                    ui.label(format!("Permanently delete {what}?"));
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "This skips the trash and cannot be undone.",
+                    );
+                    ui.horizontal(|ui| {
+                        if ui.button("Delete").clicked() {
+                            let label = format!("Permanently delete {what}");
+                            let paths = paths.clone();
+                            self.queue.enqueue(label, move |job| {
+                                let mut outcome = JobOutcome::default();
+                                for p in paths {
+                                    if job.is_cancelled() {
+                                        break;
+                                    }
+                                    if let Err(e) = fs_ops::delete_permanent(&p) {
+                                        outcome.failures.push((p, e.to_string()));
+                                    }
+                                }
+                                outcome
+                            });
+                            self.confirm_permanent_delete = None;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            self.confirm_permanent_delete = None;
+                        }
+                    });
+                });
+        }
+        if let Some((from, new_name)) = self.pending_rename.clone() {
+            egui::Window::new("Change extension?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "Rename \"{}\" to \"{new_name}\"?",
+                        from.file_name().unwrap_or_default().to_string_lossy()
+                    ));
+                    ui.label("Changing the extension may make the file unusable.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Continue").clicked() {
+                            self.apply_rename(&from, &new_name, false);
+                            self.pending_rename = None;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            self.pending_rename = None;
+                        }
+                    });
+                });
+        }
+        if let Some((from, new_name)) = self.confirm_rename_overwrite.clone() {
+            egui::Window::new("Replace existing item?")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!("\"{new_name}\" already exists in this folder."));
+                    ui.label("Replace it? The existing item is moved to the trash.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Replace").clicked() {
+                            self.confirm_rename_overwrite = None;
+                            self.apply_rename(&from, &new_name, true);
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            self.confirm_rename_overwrite = None;
+                        }
+                    });
+                });
+        }
+        if let Some((path, mut dt)) = self.mtime_dialog.clone() {
+            let mut close = false;
+            egui::Window::new("Change modified date")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(path.display().to_string());
+                    if let Ok(t) = fs_ops::modified(&path) {
+                        ui.label(format!(
+                            "Currently: {}",
+                            fs_ops::DateTime::from_system_time(t)
+                        ));
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Date");
+                        ui.add(egui::DragValue::new(&mut dt.year).range(1970..=9999));
+                        ui.label("-");
+                        ui.add(egui::DragValue::new(&mut dt.month).range(1..=12));
+                        ui.label("-");
+                        let max_day = fs_ops::DateTime::days_in_month(dt.year, dt.month);
+                        ui.add(egui::DragValue::new(&mut dt.day).range(1..=max_day));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Time");
+                        ui.add(egui::DragValue::new(&mut dt.hour).range(0..=23));
+                        ui.label(":");
+                        ui.add(egui::DragValue::new(&mut dt.minute).range(0..=59));
+                        ui.label(":");
+                        ui.add(egui::DragValue::new(&mut dt.second).range(0..=59));
+                        ui.label("UTC");
+                        if ui.button("Now").clicked() {
+                            dt = fs_ops::DateTime::from_system_time(SystemTime::now());
+                        }
+                    });
+                    let parsed = dt.to_system_time();
+                    if let Err(e) = &parsed {
+                        ui.colored_label(ui.visuals().error_fg_color, e);
+                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(parsed.is_ok(), egui::Button::new("Apply"))
+                            .clicked()
+                            && let Ok(t) = parsed
+                        {
+                            match fs_ops::set_mtime(&path, t) {
+                                Ok(op) => {
+                                    self.ops_hist.push(op);
+                                    self.browser.invalidate();
+                                }
+                                Err(e) => self
+                                    .toasts
+                                    .error(format!("Change modified date failed: {e}")),
+                            }
+                            close = true;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            self.mtime_dialog = if close { None } else { Some((path, dt)) };
+        }
+        if let Some((path, mut mode, mut octal)) = self.chmod_dialog.clone() {
+            let mut close = false;
+            egui::Window::new("Permissions")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(path.display().to_string());
+                    let mut toggled = false;
+                    egui::Grid::new("chmod-bits").show(ui, |ui| {
+                        ui.label("");
+                        for title in ["Read", "Write", "Execute"] {
+                            ui.strong(title);
+                        }
+                        ui.end_row();
+                        for (who, shift) in [("Owner", 6), ("Group", 3), ("Other", 0)] {
+                            ui.label(who);
+                            for bit in [4, 2, 1] {
+                                let mask = bit << shift;
+                                let mut on = mode & mask != 0;
+                                if ui.checkbox(&mut on, "").changed() {
+                                    mode ^= mask;
+                                    toggled = true;
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Octal");
+                        let resp = ui.add(TextEdit::singleline(&mut octal).desired_width(60.0));
+                        if resp.changed()
+                            && let Ok(m) = u32::from_str_radix(octal.trim(), 8)
+                            && m <= 0o7777
+                        {
+                            mode = m;
+                        }
+                        ui.monospace(fs_ops::format_mode(mode));
+                    });
+                    if toggled {
+                        octal = format!("{mode:o}");
+                    }
+                    let valid = u32::from_str_radix(octal.trim(), 8).is_ok_and(|m| m == mode);
+                    if !valid {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            "Enter an octal mode such as 644 or 0755",
+                        );
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(valid, Button::new("Apply")).clicked() {
+                            match fs_ops::chmod(&path, mode) {
+                                Ok(op) => {
+                                    self.ops_hist.push(op);
+                                    self.browser.invalidate();
+                                }
+                                Err(e) => {
+                                    self.toasts.error(format!("Change permissions failed: {e}"))
+                                }
+                            }
+                            close = true;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(Key::Escape))
+                        {
+                            close = true;
+                        }
+                    });
+                });
+            self.chmod_dialog = if close {
+                None
+            } else {
+                Some((path, mode, octal))
+            };
+        }
         if let Some((kind, target_dir)) = self.create_dialog.clone() {
             let title = match kind {
                 CreateKind::Folder => "Create folder",
@@ -655,7 +3471,48 @@ impl eframe::App for AppData {
     }
 }
 
+fn highlighted_path(
+    ui: &egui::Ui,
+    path: &Path,
+    range: &std::ops::Range<usize>,
+) -> egui::text::LayoutJob {
+    let full = path.display().to_string();
+    let name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let plain = egui::TextFormat {
+        font_id: egui::TextStyle::Button.resolve(ui.style()),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let strong = egui::TextFormat {
+        color: ui.visuals().strong_text_color(),
+        background: ui.visuals().selection.bg_fill,
+        ..plain.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    if range.is_empty() || range.end > name.len() || !full.ends_with(name) {
+        job.append(&full, 0.0, plain);
+        return job;
+    }
+    let start = full.len() - name.len() + range.start;
+    let end = full.len() - name.len() + range.end;
+    job.append(&full[..start], 0.0, plain.clone());
+    job.append(&full[start..end], 0.0, strong);
+    job.append(&full[end..], 0.0, plain);
+    job
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("search") {
+        std::process::exit(cli::run_search(&args[1..]));
+    }
+
+    let retention_days = config::Prefs::load().trash_retention_days;
+    std::thread::spawn(move || fs_ops::purge_trash(retention_days));
+
     let options = eframe::NativeOptions::default();
     eframe::run_native(
         "REX File Explorer",
@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn open_file(path: &Path) {
     #[cfg(target_os = "windows")]
@@ -11,6 +11,24 @@ pub fn open_file(path: &Path) {
     let _ = std::process::Command::new("open").arg(path).spawn();
 }
 
+pub fn is_hidden(entry: &std::fs::DirEntry) -> bool {
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if entry
+            .metadata()
+            .is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+        {
+            return true;
+        }
+    }
+    false
+}
+
 pub fn open_with(path: &Path, cmdline: &str) {
     // TODO quote-aware parsing
     let mut parts = cmdline.split_whitespace();
@@ -20,6 +38,33 @@ pub fn open_with(path: &Path, cmdline: &str) {
     }
 }
 
+/// Whether files can be dragged out of the window into other apps. Where it
+/// can't, the browser doesn't offer it at all.
+pub const CAN_DRAG_OUT: bool = cfg!(target_os = "windows");
+
+pub fn start_drag_out(frame: &eframe::Frame, paths: &[PathBuf]) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        let item = drag::DragItem::Files(paths.to_vec());
+        return drag::start_drag(
+            frame,
+            item,
+            drag::Image::Raw(vec![]),
+            |_, _| {},
+            drag::Options::default(),
+        )
+        .is_ok();
+    }
+
+    // TODO macOS can use the same crate once we ship a drag preview image,
+    // and Linux needs a non-GTK XDND source.
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = (frame, paths);
+        false
+    }
+}
+
 pub fn open_terminal_in(path: &Path) {
     #[cfg(target_os = "windows")]
     {
@@ -79,3 +124,34 @@ pub fn open_terminal_in(path: &Path) {
         }
     }
 }
+
+pub fn list_drives() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        ('A'..='Z')
+            .map(|c| PathBuf::from(format!("{c}:\\")))
+            .filter(|p| p.exists())
+            .collect()
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        vec![]
+    }
+}
+
+pub fn parse_unc(input: &str) -> Option<(PathBuf, PathBuf)> {
+    let s = input.trim().replace('/', "\\");
+    let rest = s.strip_prefix("\\\\")?;
+    let mut parts = rest.split('\\').filter(|p| !p.is_empty());
+    let server = parts.next().filter(|s| *s != "?" && *s != ".")?;
+    let share = parts.next()?;
+    let root = format!("\\\\{server}\\{share}\\");
+    let tail = parts.collect::<Vec<_>>().join("\\");
+    Some((PathBuf::from(&root), PathBuf::from(root + &tail)))
+}
+
+pub fn free_space(path: &Path) -> Option<(u64, u64)> {
+    let available = fs2::available_space(path).ok()?;
+    let total = fs2::total_space(path).ok()?;
+    Some((available, total))
+}
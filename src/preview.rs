@@ -0,0 +1,209 @@
+use super::{fs_ops, thumbnails};
+use eframe::egui::{self, ColorImage, Context, TextureHandle, TextureOptions, Ui};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::SystemTime,
+};
+
+const HEAD_BYTES: u64 = 64 * 1024;
+const HEX_BYTES: usize = 4 * 1024;
+const TEXT_LINES: usize = 200;
+const IMAGE_PIXELS: u32 = 512;
+
+type Key = (PathBuf, Option<SystemTime>);
+
+enum Content {
+    Folder,
+    Text(String),
+    Hex(String),
+    Image(TextureHandle),
+    Failed(String),
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| match b {
+                0x20..=0x7e => b as char,
+                _ => '.',
+            })
+            .collect();
+        out.push_str(&format!(
+            "{:08x}  {:<47}  |{ascii}|\n",
+            row * 16,
+            hex.join(" ")
+        ));
+    }
+    out
+}
+
+fn load(ctx: &Context, path: &Path) -> (Content, bool) {
+    if thumbnails::supports(path) {
+        return match image::open(path) {
+            Ok(image) => {
+                let image = image.thumbnail(IMAGE_PIXELS, IMAGE_PIXELS).to_rgba8();
+                let size = [image.width() as usize, image.height() as usize];
+                let image = ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+                let name = format!("preview:{}", path.display());
+                let texture = ctx.load_texture(name, image, TextureOptions::LINEAR);
+                (Content::Image(texture), false)
+            }
+            Err(e) => (Content::Failed(e.to_string()), false),
+        };
+    }
+
+    let mut head = Vec::new();
+    if let Err(e) = File::open(path).and_then(|f| f.take(HEAD_BYTES + 1).read_to_end(&mut head)) {
+        return (Content::Failed(e.to_string()), false);
+    }
+    let mut truncated = head.len() as u64 > HEAD_BYTES;
+    head.truncate(HEAD_BYTES as usize);
+
+    // A cut in the middle of a multi-byte character is still text.
+    let text = match std::str::from_utf8(&head) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    };
+    match text {
+        Some(text) if !text.contains('\0') => {
+            let mut lines = text.lines();
+            let shown: Vec<&str> = lines.by_ref().take(TEXT_LINES).collect();
+            truncated |= lines.next().is_some();
+            (Content::Text(shown.join("\n")), truncated)
+        }
+        _ => {
+            truncated |= head.len() > HEX_BYTES;
+            let shown = &head[..head.len().min(HEX_BYTES)];
+            (Content::Hex(hex_dump(shown)), truncated)
+        }
+    }
+}
+
+struct Loaded {
+    size: Option<u64>,
+    content: Content,
+    truncated: bool,
+}
+
+fn inspect(ctx: &Context, path: &Path) -> Loaded {
+    let (size, (content, truncated)) = match std::fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => (None, (Content::Folder, false)),
+        Ok(meta) => (Some(meta.len()), load(ctx, path)),
+        Err(e) => (None, (Content::Failed(e.to_string()), false)),
+    };
+    Loaded {
+        size,
+        content,
+        truncated,
+    }
+}
+
+pub struct Preview {
+    key: Option<Key>,
+    loaded: Option<Loaded>,
+    tx_req: Sender<(Key, Context)>,
+    rx_done: Receiver<(Key, Loaded)>,
+}
+
+impl Preview {
+    pub fn new() -> Self {
+        let (tx_req, rx_req) = mpsc::channel::<(Key, Context)>();
+        let (tx_done, rx_done) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Ok(mut req) = rx_req.recv() {
+                // Only the latest selection matters; skip whatever it superseded.
+                while let Ok(newer) = rx_req.try_recv() {
+                    req = newer;
+                }
+                let (key, ctx) = req;
+                let loaded = inspect(&ctx, &key.0);
+                if tx_done.send((key, loaded)).is_err() {
+                    break;
+                }
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            key: None,
+            loaded: None,
+            tx_req,
+            rx_done,
+        }
+    }
+
+    fn request(&mut self, ctx: &Context, key: Key) {
+        if self.key.as_ref() == Some(&key) {
+            return;
+        }
+        self.key = Some(key.clone());
+        self.loaded = None;
+        let _ = self.tx_req.send((key, ctx.clone()));
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, target: Option<(PathBuf, Option<SystemTime>)>) {
+        while let Ok((key, loaded)) = self.rx_done.try_recv() {
+            if self.key.as_ref() == Some(&key) {
+                self.loaded = Some(loaded);
+            }
+        }
+
+        let Some((path, modified)) = target else {
+            self.key = None;
+            self.loaded = None;
+            ui.weak("Select a file to preview it.");
+            return;
+        };
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        ui.heading(name);
+
+        self.request(ui.ctx(), (path, modified));
+        let Some(Loaded {
+            size,
+            content,
+            truncated,
+        }) = &self.loaded
+        else {
+            ui.spinner();
+            return;
+        };
+        if let Content::Folder = content {
+            ui.weak("Folder");
+            return;
+        }
+        if let Some(size) = size {
+            ui.weak(fs_ops::format_size(*size));
+        }
+        ui.separator();
+        if *truncated {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Showing the start only (truncated)",
+            );
+        }
+        egui::ScrollArea::both()
+            .auto_shrink([false, false])
+            .show(ui, |ui| match content {
+                Content::Text(text) | Content::Hex(text) => {
+                    ui.add(egui::Label::new(egui::RichText::new(text).monospace()).extend());
+                }
+                Content::Image(texture) => {
+                    ui.add(egui::Image::from_texture(texture).shrink_to_fit());
+                }
+                Content::Failed(reason) => {
+                    ui.colored_label(ui.visuals().error_fg_color, reason);
+                }
+                Content::Folder => {}
+            });
+    }
+}
@@ -0,0 +1,320 @@
+use super::fs_ops::{self, Op};
+use eframe::egui::{self, ProgressBar};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+};
+
+#[derive(Default)]
+pub struct JobOutcome {
+    pub ops: Vec<Op>,
+    pub failures: Vec<(PathBuf, String)>,
+}
+
+impl JobOutcome {
+    pub fn single(path: &Path, res: std::io::Result<Op>) -> Self {
+        let mut outcome = Self::default();
+        match res {
+            Ok(op) => outcome.ops.push(op),
+            Err(e) => outcome.failures.push((path.to_path_buf(), e.to_string())),
+        }
+        outcome
+    }
+}
+
+type JobFn = Box<dyn FnOnce(&JobCtx) -> JobOutcome + Send>;
+
+enum Event {
+    Started(u64),
+    Progress {
+        id: u64,
+        done: u64,
+        total: u64,
+        current: Option<PathBuf>,
+    },
+    Bytes {
+        id: u64,
+        done: u64,
+        total: u64,
+        current: PathBuf,
+    },
+    Finished(u64, JobOutcome),
+}
+
+pub struct JobCtx {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    tx: Sender<Event>,
+}
+
+impl JobCtx {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    pub fn progress(&self, done: u64, total: u64, current: Option<&Path>) {
+        let _ = self.tx.send(Event::Progress {
+            id: self.id,
+            done,
+            total,
+            current: current.map(Path::to_path_buf),
+        });
+    }
+
+    pub fn bytes(&self, done: u64, total: u64, current: &Path) {
+        let _ = self.tx.send(Event::Bytes {
+            id: self.id,
+            done,
+            total,
+            current: current.to_path_buf(),
+        });
+    }
+
+    /// Total size of the items, for byte-level progress. Gives up early if the
+    /// job is cancelled.
+    pub fn measure(&self, items: &[PathBuf]) -> u64 {
+        items
+            .iter()
+            .filter_map(|item| fs_ops::dir_size(item, &self.cancel))
+            .map(|size| size.bytes)
+            .sum()
+    }
+
+    pub fn run_each(
+        &self,
+        items: &[PathBuf],
+        mut f: impl FnMut(&Path) -> std::io::Result<Op>,
+    ) -> JobOutcome {
+        let mut outcome = JobOutcome::default();
+        let total = items.len() as u64;
+        for (i, item) in items.iter().enumerate() {
+            if self.is_cancelled() {
+                break;
+            }
+            self.progress(i as u64, total, Some(item));
+            match f(item) {
+                Ok(op) => outcome.ops.push(op),
+                Err(_) if self.is_cancelled() => break,
+                Err(e) => outcome.failures.push((item.clone(), e.to_string())),
+            }
+        }
+        self.progress(total, total, None);
+        outcome
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Pending,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+pub struct Job {
+    pub id: u64,
+    pub label: String,
+    pub state: JobState,
+    pub done: u64,
+    pub total: u64,
+    pub current: Option<PathBuf>,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub failures: usize,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+pub struct Completed {
+    pub label: String,
+    pub cancelled: bool,
+    pub outcome: JobOutcome,
+}
+
+pub struct OpQueue {
+    jobs: Vec<Job>,
+    next_id: u64,
+    tx_jobs: Sender<(u64, Arc<AtomicBool>, JobFn)>,
+    rx_events: Receiver<Event>,
+}
+
+impl OpQueue {
+    pub fn new() -> Self {
+        let (tx_jobs, rx_jobs) = mpsc::channel::<(u64, Arc<AtomicBool>, JobFn)>();
+        let (tx_events, rx_events) = mpsc::channel::<Event>();
+        std::thread::spawn(move || {
+            for (id, cancel, job) in rx_jobs {
+                if cancel.load(Ordering::Relaxed) {
+                    let _ = tx_events.send(Event::Finished(id, JobOutcome::default()));
+                    continue;
+                }
+                let _ = tx_events.send(Event::Started(id));
+                let ctx = JobCtx {
+                    id,
+                    cancel,
+                    tx: tx_events.clone(),
+                };
+                let outcome = job(&ctx);
+                let _ = tx_events.send(Event::Finished(id, outcome));
+            }
+        });
+        Self {
+            jobs: vec![],
+            next_id: 0,
+            tx_jobs,
+            rx_events,
+        }
+    }
+
+    pub fn enqueue(
+        &mut self,
+        label: impl Into<String>,
+        job: impl FnOnce(&JobCtx) -> JobOutcome + Send + 'static,
+    ) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.jobs.push(Job {
+            id,
+            label: label.into(),
+            state: JobState::Pending,
+            done: 0,
+            total: 0,
+            current: None,
+            bytes_done: 0,
+            bytes_total: 0,
+            failures: 0,
+            cancel: cancel.clone(),
+        });
+        let _ = self.tx_jobs.send((id, cancel, Box::new(job)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// The running job that reports byte-level progress, if any.
+    pub fn transfer(&self) -> Option<&Job> {
+        self.jobs
+            .iter()
+            .find(|j| j.state == JobState::Running && j.bytes_done > 0)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.jobs
+            .iter()
+            .any(|j| matches!(j.state, JobState::Pending | JobState::Running))
+    }
+
+    pub fn poll(&mut self) -> Vec<Completed> {
+        let mut completed = vec![];
+        while let Ok(ev) = self.rx_events.try_recv() {
+            match ev {
+                Event::Started(id) => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        job.state = JobState::Running;
+                    }
+                }
+                Event::Progress {
+                    id,
+                    done,
+                    total,
+                    current,
+                } => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        job.done = done;
+                        job.total = total;
+                        job.current = current;
+                    }
+                }
+                Event::Bytes {
+                    id,
+                    done,
+                    total,
+                    current,
+                } => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        job.bytes_done = done;
+                        job.bytes_total = total;
+                        job.current = Some(current);
+                    }
+                }
+                Event::Finished(id, outcome) => {
+                    if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+                        let cancelled = job.cancel.load(Ordering::Relaxed);
+                        job.state = if cancelled {
+                            JobState::Cancelled
+                        } else {
+                            JobState::Finished
+                        };
+                        job.current = None;
+                        job.failures = outcome.failures.len();
+                        completed.push(Completed {
+                            label: job.label.clone(),
+                            cancelled,
+                            outcome,
+                        });
+                    }
+                }
+            }
+        }
+        completed
+    }
+
+    pub fn draw(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Operations");
+            if ui.button("Clear finished").clicked() {
+                self.jobs
+                    .retain(|j| matches!(j.state, JobState::Pending | JobState::Running));
+            }
+        });
+        egui::ScrollArea::vertical()
+            .id_salt("op-queue")
+            .max_height(120.0)
+            .show(ui, |ui| {
+                for job in &self.jobs {
+                    ui.horizontal(|ui| {
+                        let status = match job.state {
+                            JobState::Pending => "⏸".to_string(),
+                            JobState::Running => "▶".to_string(),
+                            JobState::Cancelled => "⏹".to_string(),
+                            JobState::Finished if job.failures > 0 => {
+                                format!("❗ {} failed", job.failures)
+                            }
+                            JobState::Finished => "✔".to_string(),
+                        };
+                        ui.label(status);
+                        ui.label(&job.label);
+                        let frac = match job.state {
+                            JobState::Finished => 1.0,
+                            _ if job.total == 0 => 0.0,
+                            _ => job.done as f32 / job.total as f32,
+                        };
+                        let mut bar = ProgressBar::new(frac).desired_width(160.0);
+                        if job.total > 0 {
+                            bar = bar.text(format!("{}/{}", job.done, job.total));
+                        }
+                        let bar = ui.add(bar);
+                        if let Some(cur) = &job.current {
+                            bar.on_hover_text(cur.display().to_string());
+                        }
+                        if matches!(job.state, JobState::Pending | JobState::Running)
+                            && ui.button("❌").on_hover_text("Cancel").clicked()
+                        {
+                            job.cancel();
+                        }
+                    });
+                }
+            });
+    }
+}
@@ -1,81 +1,765 @@
+use super::platform;
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::gitignore::Gitignore;
+use regex::{Regex, RegexBuilder};
 use std::{
+    collections::HashSet,
+    fs::{DirEntry, File},
+    io::{BufRead, BufReader, Read},
+    ops::Range,
     path::{Path, PathBuf},
     sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
         mpsc::Sender,
     },
+    time::{Duration, SystemTime},
 };
 
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+const MAX_CHUNK_BYTES: u64 = 64 * 1024;
+const MAX_LINE_CHARS: usize = 200;
+
 #[derive(Clone)]
 pub struct SearchMsg {
     pub path: PathBuf,
+    pub line: Option<(u64, String)>,
+    /// Byte range of the match within the file name, empty when there is
+    /// nothing to highlight.
+    pub name_match: Range<usize>,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
 }
 
 #[derive(Clone)]
 pub struct ProgressMsg {
     pub scanned_files: u64,
     pub scanned_dirs: u64,
+    pub truncated: bool,
     pub done: bool,
 }
 
-pub fn spawn_search(
-    root: PathBuf,
-    query: String,
-    tx_results: Sender<SearchMsg>,
-    tx_prog: Sender<ProgressMsg>,
-    abort: Arc<AtomicBool>,
-) {
-    std::thread::spawn(move || {
-        fn walk(
-            dir: &Path,
-            query: &str,
-            tx_results: &Sender<SearchMsg>,
-            tx_prog: &Sender<ProgressMsg>,
-            abort: &AtomicBool,
-            counters: &mut (u64, u64),
-        ) {
-            if abort.load(Ordering::Relaxed) {
-                return;
-            }
-            let read = match std::fs::read_dir(dir) {
-                Ok(r) => r,
-                Err(_) => return,
-            };
-            counters.1 += 1;
-            let _ = tx_prog.send(ProgressMsg {
-                scanned_files: counters.0,
-                scanned_dirs: counters.1,
-                done: false,
-            });
-            for entry in read.flatten() {
-                if abort.load(Ordering::Relaxed) {
-                    return;
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchKind {
+    #[default]
+    NameOnly,
+    Content,
+    NameAndContent,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 3] = [
+        SearchKind::NameOnly,
+        SearchKind::Content,
+        SearchKind::NameAndContent,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SearchKind::NameOnly => "Names",
+            SearchKind::Content => "Contents",
+            SearchKind::NameAndContent => "Names and contents",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryMode {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl QueryMode {
+    pub const ALL: [QueryMode; 3] = [QueryMode::Substring, QueryMode::Glob, QueryMode::Regex];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            QueryMode::Substring => "substring",
+            QueryMode::Glob => "glob",
+            QueryMode::Regex => "regex",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            QueryMode::Substring => "Text",
+            QueryMode::Glob => "Glob",
+            QueryMode::Regex => "Regex",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SearchOptions {
+    pub query: String,
+    pub whole_word: bool,
+    pub mode: QueryMode,
+    pub kind: SearchKind,
+    pub max_content_bytes: u64,
+    pub threads: usize,
+    pub max_depth: Option<usize>,
+    pub extensions: HashSet<String>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub modified_within: Option<Duration>,
+    pub skip_hidden: bool,
+    pub respect_gitignore: bool,
+    pub follow_symlinks: bool,
+    pub max_results: u64,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            whole_word: false,
+            mode: QueryMode::Substring,
+            kind: SearchKind::NameOnly,
+            max_content_bytes: 10 * 1024 * 1024,
+            threads: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            max_depth: None,
+            extensions: HashSet::new(),
+            min_size: None,
+            max_size: None,
+            modified_within: None,
+            skip_hidden: true,
+            respect_gitignore: false,
+            follow_symlinks: true,
+            max_results: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub path: PathBuf,
+    pub line: Option<(u64, String)>,
+    pub name_match: Range<usize>,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Progress {
+    pub scanned_files: u64,
+    pub scanned_dirs: u64,
+    pub truncated: bool,
+}
+
+// ASCII letters, digits and `_`, the same set as the regex `(?-u:\b)` used
+// for whole-word regex searches.
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn find_whole_word(name: &str, query: &str) -> Option<usize> {
+    if query.is_empty() {
+        return None;
+    }
+    name.match_indices(query)
+        .find(|(start, m)| {
+            let before = name[..*start].chars().next_back();
+            let after = name[start + m.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+        .map(|(start, _)| start)
+}
+
+pub fn matches_whole_word(name: &str, query: &str) -> bool {
+    find_whole_word(name, query).is_some()
+}
+
+pub fn fuzzy_score(candidate: &str, query: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev = None::<usize>;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + candidate[pos..].iter().position(|c| *c == q)?;
+        score += 1;
+        if prev.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !is_word_char(candidate[found - 1]) {
+            score += 3;
+        }
+        prev = Some(found);
+        pos = found + 1;
+    }
+    Some(score * 100 - candidate.len() as i64)
+}
+
+enum Pattern {
+    Substring { query: String, whole_word: bool },
+    Glob { glob: GlobMatcher, on_path: bool },
+    Regex(Regex),
+}
+
+pub struct Matcher {
+    pattern: Pattern,
+}
+
+impl Matcher {
+    pub fn new(options: &SearchOptions) -> Result<Self, String> {
+        let pattern = match options.mode {
+            QueryMode::Substring => Pattern::Substring {
+                query: options.query.to_lowercase(),
+                whole_word: options.whole_word,
+            },
+            QueryMode::Glob => {
+                let query = options.query.replace('\\', "/");
+                let glob = GlobBuilder::new(&query)
+                    .case_insensitive(true)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| e.to_string())?
+                    .compile_matcher();
+                Pattern::Glob {
+                    glob,
+                    on_path: query.contains('/'),
                 }
-                let path = entry.path();
-                if path.is_dir() {
-                    walk(&path, query, tx_results, tx_prog, abort, counters);
+            }
+            QueryMode::Regex => {
+                let source = if options.whole_word {
+                    format!(r"(?-u:\b)(?:{})(?-u:\b)", options.query)
                 } else {
-                    counters.0 += 1;
-                    if let Some(name) = path.file_name().and_then(|s| s.to_str()) {
-                        if name.to_lowercase().contains(&query.to_lowercase()) {
-                            let _ = tx_results.send(SearchMsg { path: path.clone() });
-                        }
-                    }
-                    let _ = tx_prog.send(ProgressMsg {
-                        scanned_files: counters.0,
-                        scanned_dirs: counters.1,
-                        done: false,
+                    options.query.clone()
+                };
+                let re = RegexBuilder::new(&source)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                Pattern::Regex(re)
+            }
+        };
+        Ok(Self { pattern })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.pattern {
+            Pattern::Substring { query, whole_word } => {
+                let text = text.to_lowercase();
+                if *whole_word {
+                    matches_whole_word(&text, query)
+                } else {
+                    text.contains(query)
+                }
+            }
+            Pattern::Glob { glob, .. } => glob.is_match(text),
+            Pattern::Regex(re) => re.is_match(text),
+        }
+    }
+
+    fn find(&self, text: &str) -> Option<Range<usize>> {
+        match &self.pattern {
+            Pattern::Substring { query, whole_word } => {
+                let lower = text.to_lowercase();
+                let start = if *whole_word {
+                    find_whole_word(&lower, query)?
+                } else {
+                    lower.find(query.as_str())?
+                };
+                // Lowercasing can change byte lengths, in which case the
+                // offsets no longer line up with the original text.
+                if lower.len() == text.len() {
+                    Some(start..start + query.len())
+                } else {
+                    Some(0..0)
+                }
+            }
+            Pattern::Glob { glob, .. } => glob.is_match(text).then_some(0..text.len()),
+            Pattern::Regex(re) => re.find(text).map(|m| m.range()),
+        }
+    }
+
+    fn name_match(&self, root: &Path, path: &Path) -> Option<Range<usize>> {
+        let name = path.file_name().and_then(|s| s.to_str())?;
+        if let Pattern::Glob {
+            glob,
+            on_path: true,
+        } = &self.pattern
+        {
+            let rel = path.strip_prefix(root).ok()?;
+            return glob.is_match(rel).then_some(0..name.len());
+        }
+        self.find(name)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    files: AtomicU64,
+    dirs: AtomicU64,
+    results: AtomicU64,
+    truncated: AtomicBool,
+}
+
+impl Counters {
+    fn snapshot(&self) -> Progress {
+        Progress {
+            scanned_files: self.files.load(Ordering::Relaxed),
+            scanned_dirs: self.dirs.load(Ordering::Relaxed),
+            truncated: self.truncated.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Pending {
+    dir: PathBuf,
+    depth: usize,
+    // Ignore rules from the search root down to `dir`, innermost last.
+    ignores: Arc<Vec<Gitignore>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<Pending>,
+    active: usize,
+}
+
+type ResultSink<'a> = Mutex<&'a mut (dyn FnMut(SearchResult) + Send)>;
+type ProgressSink<'a> = Mutex<&'a mut (dyn FnMut(Progress) + Send)>;
+
+struct Walker<'a> {
+    root: PathBuf,
+    matcher: Matcher,
+    kind: SearchKind,
+    max_content_bytes: u64,
+    max_depth: Option<usize>,
+    extensions: &'a HashSet<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_since: Option<SystemTime>,
+    skip_hidden: bool,
+    respect_gitignore: bool,
+    follow_symlinks: bool,
+    max_results: u64,
+    // Canonical paths of every directory queued so far, so a symlink cycle
+    // can't send the walk round in circles.
+    visited: Mutex<HashSet<PathBuf>>,
+    abort: &'a AtomicBool,
+    counters: Counters,
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    on_result: ResultSink<'a>,
+    on_progress: ProgressSink<'a>,
+}
+
+impl Walker<'_> {
+    fn content_match(&self, path: &Path) -> Option<(u64, String)> {
+        let file = File::open(path).ok()?;
+        if file.metadata().ok()?.len() > self.max_content_bytes {
+            return None;
+        }
+        let mut reader = BufReader::with_capacity(MAX_CHUNK_BYTES as usize, file);
+        let head = reader.fill_buf().ok()?;
+        if head.iter().take(BINARY_SNIFF_BYTES).any(|b| *b == 0) {
+            return None;
+        }
+        let mut chunk = Vec::new();
+        let mut line_no = 0;
+        let mut at_line_start = true;
+        loop {
+            if self.stopped() {
+                return None;
+            }
+            chunk.clear();
+            let n = (&mut reader)
+                .take(MAX_CHUNK_BYTES)
+                .read_until(b'\n', &mut chunk)
+                .ok()?;
+            if n == 0 {
+                return None;
+            }
+            if at_line_start {
+                line_no += 1;
+            }
+            at_line_start = chunk.ends_with(b"\n");
+            let text = String::from_utf8_lossy(&chunk);
+            if self.matcher.is_match(&text) {
+                let shown: String = text.trim().chars().take(MAX_LINE_CHARS).collect();
+                return Some((line_no, shown));
+            }
+        }
+    }
+
+    fn passes_filters(&self, entry: &DirEntry, path: &Path) -> bool {
+        let ext_ok = self.extensions.is_empty()
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| self.extensions.contains(&e.to_lowercase()));
+        if !ext_ok {
+            return false;
+        }
+        if self.min_size.is_none() && self.max_size.is_none() && self.modified_since.is_none() {
+            return true;
+        }
+        let Ok(meta) = entry.metadata() else {
+            return false;
+        };
+        let len = meta.len();
+        let size_ok = (self.min_size.is_none() && self.max_size.is_none())
+            || (meta.is_file()
+                && self.min_size.is_none_or(|min| len >= min)
+                && self.max_size.is_none_or(|max| len <= max));
+        size_ok
+            && self
+                .modified_since
+                .is_none_or(|since| meta.modified().is_ok_and(|m| m >= since))
+    }
+
+    fn stopped(&self) -> bool {
+        self.abort.load(Ordering::Relaxed) || self.counters.truncated.load(Ordering::Relaxed)
+    }
+
+    fn emit(&self, result: SearchResult) {
+        if self.counters.results.fetch_add(1, Ordering::Relaxed) >= self.max_results {
+            self.counters.truncated.store(true, Ordering::Relaxed);
+            return;
+        }
+        if let Ok(mut on_result) = self.on_result.lock() {
+            on_result(result);
+        }
+    }
+
+    fn report(&self) {
+        if let Ok(mut on_progress) = self.on_progress.lock() {
+            on_progress(self.counters.snapshot());
+        }
+    }
+
+    // Blocks until a directory is available, or returns None once every
+    // worker is idle with nothing queued (or the search was aborted).
+    fn next_dir(&self) -> Option<Pending> {
+        let mut queue = self.queue.lock().ok()?;
+        loop {
+            if self.stopped() {
+                return None;
+            }
+            if let Some(dir) = queue.pending.pop() {
+                queue.active += 1;
+                return Some(dir);
+            }
+            if queue.active == 0 {
+                return None;
+            }
+            queue = self.ready.wait(queue).ok()?;
+        }
+    }
+
+    fn finish_dir(&self, subdirs: Vec<Pending>) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.pending.extend(subdirs);
+            queue.active -= 1;
+        }
+        self.ready.notify_all();
+    }
+
+    fn work(&self) {
+        while let Some(pending) = self.next_dir() {
+            let subdirs = self.scan(pending);
+            self.finish_dir(subdirs);
+        }
+        // Wake idle workers so they notice an abort or the end of the walk.
+        self.ready.notify_all();
+    }
+
+    fn is_ignored(ignores: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+        if is_dir && path.file_name().is_some_and(|n| n == ".git") {
+            return true;
+        }
+        for rules in ignores.iter().rev() {
+            let m = rules.matched(path, is_dir);
+            if !m.is_none() {
+                return m.is_ignore();
+            }
+        }
+        false
+    }
+
+    fn first_visit(&self, dir: &Path) -> bool {
+        let Ok(canonical) = dir.canonicalize() else {
+            return false;
+        };
+        self.visited
+            .lock()
+            .is_ok_and(|mut visited| visited.insert(canonical))
+    }
+
+    fn scan(
+        &self,
+        Pending {
+            dir,
+            depth,
+            ignores,
+        }: Pending,
+    ) -> Vec<Pending> {
+        let mut subdirs = vec![];
+        let read = match std::fs::read_dir(&dir) {
+            Ok(r) => r,
+            Err(_) => return subdirs,
+        };
+        let gitignore = dir.join(".gitignore");
+        let ignores = if self.respect_gitignore && gitignore.is_file() {
+            let (rules, _) = Gitignore::new(gitignore);
+            let mut chain = ignores.as_ref().clone();
+            chain.push(rules);
+            Arc::new(chain)
+        } else {
+            ignores
+        };
+        self.counters.dirs.fetch_add(1, Ordering::Relaxed);
+        self.report();
+        for entry in read.flatten() {
+            if self.stopped() {
+                break;
+            }
+            if self.skip_hidden && platform::is_hidden(&entry) {
+                continue;
+            }
+            let path = entry.path();
+            let is_dir = if self.follow_symlinks {
+                path.is_dir()
+            } else {
+                entry.file_type().is_ok_and(|t| t.is_dir())
+            };
+            if self.respect_gitignore && Self::is_ignored(&ignores, &path, is_dir) {
+                continue;
+            }
+            if is_dir {
+                if self.max_depth.is_none_or(|max| depth < max) && self.first_visit(&path) {
+                    subdirs.push(Pending {
+                        dir: path,
+                        depth: depth + 1,
+                        ignores: ignores.clone(),
                     });
                 }
+            } else {
+                self.counters.files.fetch_add(1, Ordering::Relaxed);
+                if !self.passes_filters(&entry, &path) {
+                    self.report();
+                    continue;
+                }
+                let name_match = match self.kind {
+                    SearchKind::Content => None,
+                    _ => self.matcher.name_match(&self.root, &path),
+                };
+                let line = if name_match.is_some() {
+                    None
+                } else if self.kind != SearchKind::NameOnly
+                    && let Some(line) = self.content_match(&path)
+                {
+                    Some(line)
+                } else {
+                    self.report();
+                    continue;
+                };
+                let meta = entry.metadata().ok();
+                self.emit(SearchResult {
+                    path: path.clone(),
+                    line,
+                    name_match: name_match.unwrap_or_default(),
+                    size: meta.as_ref().map_or(0, |m| m.len()),
+                    modified: meta.and_then(|m| m.modified().ok()),
+                });
+                self.report();
             }
         }
-        let mut counters = (0u64, 0u64);
-        walk(&root, &query, &tx_results, &tx_prog, &abort, &mut counters);
+        subdirs
+    }
+}
+
+fn run(
+    root: &Path,
+    options: &SearchOptions,
+    matcher: Matcher,
+    abort: &AtomicBool,
+    mut on_result: impl FnMut(SearchResult) + Send,
+    mut on_progress: impl FnMut(Progress) + Send,
+) -> Progress {
+    let walker = Walker {
+        root: root.to_path_buf(),
+        matcher,
+        kind: options.kind,
+        max_content_bytes: options.max_content_bytes,
+        max_depth: options.max_depth,
+        extensions: &options.extensions,
+        min_size: options.min_size,
+        max_size: options.max_size,
+        modified_since: options.modified_within.map(|d| {
+            SystemTime::now()
+                .checked_sub(d)
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        }),
+        skip_hidden: options.skip_hidden,
+        respect_gitignore: options.respect_gitignore,
+        follow_symlinks: options.follow_symlinks,
+        max_results: options.max_results,
+        visited: Mutex::new(root.canonicalize().into_iter().collect()),
+        abort,
+        counters: Counters::default(),
+        queue: Mutex::new(Queue {
+            pending: vec![Pending {
+                dir: root.to_path_buf(),
+                depth: 0,
+                ignores: Arc::new(vec![]),
+            }],
+            active: 0,
+        }),
+        ready: Condvar::new(),
+        on_result: Mutex::new(&mut on_result),
+        on_progress: Mutex::new(&mut on_progress),
+    };
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.max(1) {
+            scope.spawn(|| walker.work());
+        }
+    });
+    walker.counters.snapshot()
+}
+
+pub fn search(
+    root: &Path,
+    options: &SearchOptions,
+    abort: &AtomicBool,
+    on_result: impl FnMut(SearchResult) + Send,
+    on_progress: impl FnMut(Progress) + Send,
+) -> Result<Progress, String> {
+    let matcher = Matcher::new(options)?;
+    Ok(run(root, options, matcher, abort, on_result, on_progress))
+}
+
+pub fn spawn_search(
+    root: PathBuf,
+    options: SearchOptions,
+    tx_results: Sender<SearchMsg>,
+    tx_prog: Sender<ProgressMsg>,
+    abort: Arc<AtomicBool>,
+) -> Result<(), String> {
+    let matcher = Matcher::new(&options)?;
+    std::thread::spawn(move || {
+        let total = run(
+            &root,
+            &options,
+            matcher,
+            &abort,
+            |r| {
+                let _ = tx_results.send(SearchMsg {
+                    path: r.path,
+                    line: r.line,
+                    name_match: r.name_match,
+                    size: r.size,
+                    modified: r.modified,
+                });
+            },
+            |p| {
+                let _ = tx_prog.send(ProgressMsg {
+                    scanned_files: p.scanned_files,
+                    scanned_dirs: p.scanned_dirs,
+                    truncated: p.truncated,
+                    done: false,
+                });
+            },
+        );
         let _ = tx_prog.send(ProgressMsg {
-            scanned_files: counters.0,
-            scanned_dirs: counters.1,
+            scanned_files: total.scanned_files,
+            scanned_dirs: total.scanned_dirs,
+            truncated: total.truncated,
             done: true,
         });
     });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fs_ops::scratch;
+    use super::*;
+
+    fn found(root: &Path, options: &SearchOptions) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        search(
+            root,
+            options,
+            &AtomicBool::new(false),
+            |r| paths.push(r.path),
+            |_| {},
+        )
+        .unwrap();
+        paths.sort();
+        paths
+    }
+
+    fn whole_word(mode: QueryMode, query: &str) -> Matcher {
+        Matcher::new(&SearchOptions {
+            query: query.into(),
+            whole_word: true,
+            mode,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn whole_word_skips_longer_words() {
+        let m = whole_word(QueryMode::Substring, "report");
+        assert!(m.is_match("report.txt"));
+        assert!(m.is_match("2024 Report-final.pdf"));
+        assert!(!m.is_match("reporter.txt"));
+        assert!(!m.is_match("my_report.txt"));
+        assert!(!m.is_match("report2.txt"));
+    }
+
+    #[test]
+    fn whole_word_agrees_across_modes() {
+        let names = ["report.txt", "reporter.txt", "éreport.txt", "report_1.txt"];
+        let substring = whole_word(QueryMode::Substring, "report");
+        let regex = whole_word(QueryMode::Regex, "report");
+        for name in names {
+            assert_eq!(substring.is_match(name), regex.is_match(name), "{name}");
+        }
+        assert!(substring.is_match("éreport.txt"));
+    }
+
+    #[test]
+    fn whole_word_search_finds_only_the_word() {
+        let root = scratch("search-whole-word");
+        std::fs::write(root.join("report.txt"), "").unwrap();
+        std::fs::write(root.join("reporter.txt"), "").unwrap();
+        let options = SearchOptions {
+            query: "report".into(),
+            whole_word: true,
+            ..Default::default()
+        };
+        assert_eq!(found(&root, &options), [root.join("report.txt")]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop_is_walked_once() {
+        let root = scratch("search-loop");
+        let dir = root.join("dir");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("needle.txt"), "").unwrap();
+        std::fs::write(root.join("needle.md"), "").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("loop")).unwrap();
+        let options = SearchOptions {
+            query: "needle".into(),
+            threads: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            found(&root, &options),
+            [root.join("dir/needle.txt"), root.join("needle.md")]
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -0,0 +1,108 @@
+use eframe::egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    time::SystemTime,
+};
+
+const THUMB_PIXELS: u32 = 64;
+const MAX_TEXTURES: usize = 256;
+
+type Key = (PathBuf, Option<SystemTime>);
+
+pub fn supports(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "gif" | "webp")
+}
+
+fn decode(path: &Path) -> Option<ColorImage> {
+    let image = image::open(path)
+        .ok()?
+        .thumbnail(THUMB_PIXELS, THUMB_PIXELS)
+        .to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+pub struct Thumbnails {
+    // Failed decodes are cached as None so they aren't retried every frame.
+    cache: HashMap<Key, Option<TextureHandle>>,
+    recent: VecDeque<Key>,
+    pending: HashSet<Key>,
+    tx_req: Sender<Key>,
+    rx_done: Receiver<(Key, Option<ColorImage>)>,
+}
+
+impl Thumbnails {
+    pub fn new(workers: usize) -> Self {
+        let (tx_req, rx_req) = mpsc::channel::<Key>();
+        let (tx_done, rx_done) = mpsc::channel();
+        let rx_req = Arc::new(Mutex::new(rx_req));
+        for _ in 0..workers.max(1) {
+            let rx_req = rx_req.clone();
+            let tx_done = tx_done.clone();
+            std::thread::spawn(move || {
+                loop {
+                    let Ok(key) = rx_req.lock().unwrap().recv() else {
+                        break;
+                    };
+                    let image = decode(&key.0);
+                    if tx_done.send((key, image)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Self {
+            cache: HashMap::new(),
+            recent: VecDeque::new(),
+            pending: HashSet::new(),
+            tx_req,
+            rx_done,
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub fn poll(&mut self, ctx: &Context) {
+        while let Ok((key, image)) = self.rx_done.try_recv() {
+            self.pending.remove(&key);
+            let texture = image.map(|image| {
+                let name = format!("thumb:{}", key.0.display());
+                ctx.load_texture(name, image, TextureOptions::LINEAR)
+            });
+            self.cache.insert(key.clone(), texture);
+            self.recent.push_back(key);
+            while self.recent.len() > MAX_TEXTURES {
+                if let Some(old) = self.recent.pop_front() {
+                    self.cache.remove(&old);
+                }
+            }
+        }
+    }
+
+    pub fn get(&mut self, path: &Path, modified: Option<SystemTime>) -> Option<TextureHandle> {
+        let key = (path.to_path_buf(), modified);
+        if let Some(texture) = self.cache.get(&key) {
+            let texture = texture.clone();
+            if let Some(i) = self.recent.iter().position(|k| *k == key) {
+                self.recent.remove(i);
+            }
+            self.recent.push_back(key);
+            return texture;
+        }
+        if self.pending.insert(key.clone()) {
+            let _ = self.tx_req.send(key);
+        }
+        None
+    }
+}